@@ -1,18 +1,34 @@
+use crate::app::start::{self, expand_home, Browser, FormField, NewDiaryForm, Summary};
+use crate::clipboard;
 use crate::commit;
-use crate::text::gapbuffer::GapBuffer;
-use crate::config::DiaryConfig;
+use crate::text::editarea::EditArea;
+use crate::text::grapheme;
+use crate::text::killring::KillRing;
+use crate::config::{DiaryConfig, MruSort};
+use crate::export::{self, Format};
+use crate::store;
+use crate::keymap::vi::{ViOutcome, ViState};
+use crate::keymap::action::{Action, Context};
+use crate::keymap::{emacs, Keymap, KeymapProfile};
+use crate::theme::{self, Theme};
+use crate::ui::markdown;
 use crate::util::current_time_string;
-use std::fs::canonicalize;
+use std::fs::{self, canonicalize};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use chrono::prelude::{DateTime, Local, Utc};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use tui::layout::Rect;
 use tui::widgets::ListState;
 
 #[derive(PartialEq)]
@@ -23,12 +39,18 @@ pub enum EditorMode {
     Writing,
     Editing,
     Saving,
+    // asking where to export the diary to.
+    Exporting,
 }
 
 // Contains the state of the application.
 pub struct App {
     // the configuration of the application.
     pub config: DiaryConfig,
+    // what every key does, built from the config.
+    pub keymap: Keymap,
+    // what everything is drawn with, also from the config.
+    pub theme: Theme,
     // what screen am I on right now?
     pub routes: Vec<AppRoute>,
 
@@ -36,11 +58,17 @@ pub struct App {
     pub file: commit::Diary,
 
     // the state of the current input
-    pub input: GapBuffer,
+    pub input: EditArea,
 
     // the state of the current edit
     pub edit: Option<Edit>,
 
+    // everything cut or copied from the input bar, shared between writing and editing.
+    pub kill_ring: KillRing,
+
+    // the vi layer over the input bar, when the vi keymap is on.
+    pub vi: ViState,
+
     // the current input mode: am I writing right now?
     pub mode: EditorMode,
     // the status bar message
@@ -49,6 +77,9 @@ pub struct App {
     // temporary input, used for anything where you need a general input for a single screen.
     pub temp_input: String,
 
+    // where the help screen is scrolled to and what it's filtered by.
+    pub help: HelpState,
+
     // state of the message view - do I have something selected right now?
     // I either have nothing selected (input, I guess), or I have a message selected.
     // I can press I to enter Input from normal no matter where I am.  I can press E while
@@ -56,6 +87,92 @@ pub struct App {
     // ListState works by telling it whe index you are selecting (Some(index)), or nothing if you
     // are not (None).
     pub select_state: ListState,
+    // how far the mru list is scrolled, i.e. the first item on screen.
+    pub list_offset: usize,
+    // the diary was opened with --read-only: nothing can be written or saved.
+    pub read_only: bool,
+    // a look inside each mru entry, in the same order, or why it couldn't be read.
+    pub recent: Vec<Result<Summary, String>>,
+    // the file browser, while it's open.
+    pub browser: Option<Browser>,
+    // the new diary form, while it's open.
+    pub new_diary: Option<NewDiaryForm>,
+    // where the message view is scrolled to; it's separate from the selection.
+    pub view: MessageView,
+
+    // where the last frame drew the things you can click on.
+    pub mouse: MouseTargets,
+    // the terminal is too small to draw the current screen on.
+    pub too_small: bool,
+    // when and where the last left click was, to spot a double click.
+    last_click: Option<(Instant, u16, u16)>,
+}
+
+// The message view's viewport, in screen rows.  The edit screen clamps `scroll` and fills in the
+// size every time it draws.
+#[derive(Default)]
+pub struct MessageView {
+    // how many rows of messages are hidden below the view; 0 means the latest is in view.
+    pub scroll: usize,
+    // the size of the view, as last drawn.
+    pub height: usize,
+    pub width: usize,
+    // messages that came in below the view while scrolled up.
+    pub unseen: usize,
+    // scroll to the selected message next time the view is drawn.
+    pub reveal: bool,
+}
+
+impl MessageView {
+    // carry out a scrolling action.  returns false if it wasn't one.
+    pub fn scroll(&mut self, action: Action) -> bool {
+        let page = usize::max(1, self.height.saturating_sub(1));
+        match action {
+            Action::PageUp => self.scroll += page,
+            Action::PageDown => self.scroll = self.scroll.saturating_sub(page),
+            // the screen clamps this to the top.
+            Action::Oldest => self.scroll = usize::MAX,
+            Action::Latest => self.scroll = 0,
+            _ => return false,
+        }
+        true
+    }
+    // a new message goes in at the bottom.  if the view is scrolled up, keep it on what it was
+    // showing instead of letting everything shift up, and count the message as unseen.  the rows
+    // it takes are counted the way the screen will draw it.
+    pub fn arrived(&mut self, text: &str, theme: &Theme) {
+        if self.scroll > 0 {
+            self.scroll += markdown::render(text, self.width, theme).len();
+            self.unseen += 1;
+        }
+    }
+}
+
+// two clicks on the same cell within this long are a double click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+// The screen drew these, so a click can be matched to what was under it.  Only the current
+// route's screen fills them in.
+#[derive(Default)]
+pub struct MouseTargets {
+    // the message view or the mru list.
+    pub list: Rect,
+    // the rows each visible list item covers: (first row, row past the last, item index).
+    pub rows: Vec<(u16, u16, usize)>,
+    // the inside of the input bar.
+    pub input: Rect,
+    // the visible lines of the input bar, and where each starts in the buffer.
+    pub input_lines: Vec<(usize, String)>,
+}
+
+#[derive(Default)]
+pub struct HelpState {
+    // how many lines the command list is scrolled down.  the screen clamps it when drawing.
+    pub scroll: usize,
+    // only commands matching this are listed.
+    pub search: String,
+    // is the search box taking keystrokes?
+    pub searching: bool,
 }
 
 // TODO: use this instead of just diary.
@@ -67,6 +184,8 @@ struct FileState {
 #[derive(PartialEq, Clone, Copy)]
 pub enum AppRoute {
     Start,
+    Browse,
+    NewDiary,
     Edit,
     Help,
     PreQuit,
@@ -74,19 +193,106 @@ pub enum AppRoute {
 }
 
 pub struct Edit {
-    edit_input: String,
+    edit_input: EditArea,
     index: usize,
 }
 
 impl Edit {
     fn from(edit_input: String, index: usize) -> Self {
-        Edit { edit_input, index }
+        Edit {
+            edit_input: EditArea::from_string(edit_input),
+            index,
+        }
     }
     pub fn get(&self) -> String {
-        self.edit_input.clone()
+        self.edit_input.to_string()
+    }
+    pub fn input(&self) -> &EditArea {
+        &self.edit_input
     }
     pub fn update(&mut self, s: String) {
-        self.edit_input = s;
+        self.edit_input = EditArea::from_string(s);
+    }
+}
+
+// the actions that edit text, which work the same in the input bar and when editing a message.
+// returns false if the action isn't one of them.
+fn edit_input_action(
+    input: &mut EditArea,
+    kill_ring: &mut KillRing,
+    config: &DiaryConfig,
+    action: Action,
+) -> bool {
+    match action {
+        Action::Undo => input.undo(),
+        Action::Redo => input.redo(),
+        Action::ToggleMark => input.toggle_mark(),
+        Action::Copy => {
+            if let Some(text) = input.selected_text() {
+                input.clear_selection();
+                kill(kill_ring, config, text);
+            }
+        }
+        Action::Cut => {
+            if let Some(text) = input.cut_selection() {
+                kill(kill_ring, config, text);
+            }
+        }
+        Action::Paste => {
+            if let Some(text) = kill_ring.yank() {
+                input.insert_str(text);
+            }
+        }
+        Action::Left => input.left(),
+        Action::Right => input.right(),
+        Action::SelectLeft => input.select_left(),
+        Action::SelectRight => input.select_right(),
+        Action::Backspace => input.back(),
+        Action::Delete => input.delete(),
+        Action::LineStart => emacs::line_start(input),
+        Action::LineEnd => emacs::line_end(input),
+        Action::WordForward => emacs::forward_word(input),
+        Action::WordBack => emacs::backward_word(input),
+        Action::KillLine => kill_ring.push(emacs::kill_line(input)),
+        Action::KillLineBack => kill_ring.push(emacs::kill_line_back(input)),
+        Action::KillWordBack => kill_ring.push(emacs::kill_word_back(input)),
+        Action::Transpose => emacs::transpose(input),
+        _ => return false,
+    }
+    true
+}
+
+// a key that isn't bound to anything types itself, as long as it's a plain character.
+fn typed_char(key: &KeyEvent) -> Option<char> {
+    match key.code {
+        KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            Some(c)
+        }
+        _ => None,
+    }
+}
+
+fn contains(rect: Rect, col: u16, row: u16) -> bool {
+    col >= rect.x && col < rect.right() && row >= rect.y && row < rect.bottom()
+}
+
+// put cut or copied text in the kill ring, and on the system clipboard if configured to.
+fn kill(kill_ring: &mut KillRing, config: &DiaryConfig, text: String) {
+    if config.osc52_clipboard {
+        // not much we can do if the terminal won't take it; the kill ring still has it.
+        let _ = clipboard::osc52_copy(&text);
+    }
+    kill_ring.push(text);
+}
+
+// where an export goes unless told otherwise: a web page beside the diary.
+fn export_name(diary: &str) -> String {
+    match diary {
+        "" => format!("diary.{}", Format::Html.extension()),
+        name => Path::new(name)
+            .with_extension(Format::Html.extension())
+            .to_string_lossy()
+            .into_owned(),
     }
 }
 
@@ -99,14 +305,21 @@ impl App {
             return Ok(false);
         }
         if let Ok(true) = event::poll(std::time::Duration::from_secs(0)) {
-            if let Event::Key(key) = event::read()? {
-                match self.routes.last().expect("self.routes should have an element as checked above") {
+            match event::read()? {
+                Event::Key(key) => match self.routes.last().expect("self.routes should have an element as checked above") {
                     AppRoute::Start => self.run_start(key),
+                    AppRoute::Browse => self.run_browse(key),
+                    AppRoute::NewDiary => self.run_new_diary(key),
                     AppRoute::Edit => self.run_edit(key),
                     AppRoute::Help => self.run_help(key),
                     AppRoute::PreQuit => self.run_prequit(key), // TODO should run a quit protocol - if not saved, don't quit yet, try and confirm!
-                    _ => unreachable!(),
-                }
+                },
+                Event::Paste(text) => self.run_paste(text),
+                Event::Mouse(mouse) if !self.too_small => self.run_mouse(mouse),
+                // the next draw fits everything to the new size; the messages get wrapped
+                // differently, so bring the selected one back into view.
+                Event::Resize(_, _) => self.view.reveal = true,
+                _ => (),
             }
         }
         Ok(true)
@@ -114,99 +327,297 @@ impl App {
     pub fn route(&self) -> Option<AppRoute> {
         self.routes.last().copied()
     }
+    // the route the help screen was opened from, i.e. the one under it.
+    pub fn help_origin(&self) -> Option<AppRoute> {
+        self.routes.iter().rev().nth(1).copied()
+    }
+    // which keymap context a route is in; on the edit screen, that depends on the mode.
+    pub fn context_of(&self, route: AppRoute) -> Context {
+        match route {
+            AppRoute::Start => Context::Start,
+            AppRoute::Browse => Context::Browse,
+            AppRoute::NewDiary => Context::NewDiary,
+            AppRoute::Edit => match self.mode {
+                EditorMode::Normal => Context::Normal,
+                EditorMode::Writing => Context::Writing,
+                EditorMode::Editing => Context::Editing,
+                EditorMode::Saving | EditorMode::Exporting => Context::Saving,
+            },
+            AppRoute::Help => Context::Help,
+            AppRoute::PreQuit => Context::PreQuit,
+        }
+    }
     fn quit(&mut self) {
         self.routes.clear();
     }
     fn run_start(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Char('q') => self.route_to(AppRoute::PreQuit),
-            KeyCode::Char('h') => self.route_to(AppRoute::Help),
-            KeyCode::Char('n') => {
-                self.route_to(AppRoute::Edit);
-            }
-            KeyCode::Enter => {
-                if let Some(msg_idx) = self.selected() {
-                    // TODO app should open an mru list stored somewhere, find the appropriate
-                    // index, then open that file and transition into AppRoute::Edit
-
-                    // open the file and completely disregard the new file created by default.
-                    // too lazy to refactor app.file into Option<Diary>...
-                    if let Some(filepath) = self.config.mru.get(msg_idx) {
-                        // TODO: filepath should be absolute.
-                        if let Ok(diary) = commit::Diary::read_from_path(filepath) {
-                            self.file = diary;
-                            self.config.update_mru_with(filepath.into());
-                        } else {
-                            eprintln!("Err: failed to open {} into a valid diary.  This is probably because Rust failed to parse the file at the path.", filepath);
-                        }
-                    } else {
-                        eprintln!(
-                            "Err: ui has {} selected but that entry is not found in app.start.mru",
-                            msg_idx
-                        );
+        match self.keymap.action(Context::Start, &key) {
+            Some(Action::Quit) => self.route_to(AppRoute::PreQuit),
+            Some(Action::Help) => self.route_to(AppRoute::Help),
+            Some(Action::NewDiary) => self.route_to(AppRoute::NewDiary),
+            Some(Action::Browse) => self.route_to(AppRoute::Browse),
+            Some(Action::Open) => self.open_selected(),
+            // the mru list is drawn top to bottom, the opposite of the message view.
+            Some(Action::SelectUp) => self.select_down(self.config.mru.len()),
+            Some(Action::SelectDown) => self.select_up(self.config.mru.len()),
+            Some(Action::Unselect) => self.unselect(),
+            Some(Action::TogglePin) => {
+                if let Some(entry) = self.selected().and_then(|i| self.config.mru.get_mut(i)) {
+                    entry.pinned = !entry.pinned;
+                    let path = entry.path.clone();
+                    self.mru_changed(Some(path));
+                }
+            }
+            // only the entry goes; the diary itself stays where it is.
+            Some(Action::Remove) => {
+                if let Some(i) = self.selected().filter(|&i| i < self.config.mru.len()) {
+                    let entry = self.config.mru.remove(i);
+                    self.unselect();
+                    self.mru_changed(None);
+                    if self.status_msg.is_empty() {
+                        self.set_status(format!("removed {} from the list", entry.title()));
                     }
-
-                    self.route_to(AppRoute::Edit);
                 }
             }
-            KeyCode::Up => self.select_down(self.config.mru.len()),
-            KeyCode::Down => self.select_up(self.config.mru.len()),
-            KeyCode::Esc => self.unselect(),
+            Some(Action::Prune) => {
+                let selected = self.selected_mru_path();
+                let pruned = self.config.prune_mru();
+                self.mru_changed(selected);
+                if self.status_msg.is_empty() {
+                    self.set_status(match pruned {
+                        0 => String::from("nothing's missing"),
+                        1 => String::from("forgot 1 missing diary"),
+                        n => format!("forgot {} missing diaries", n),
+                    });
+                }
+            }
+            Some(Action::ToggleSort) => {
+                self.config.mru_sort = match self.config.mru_sort {
+                    MruSort::Recent => MruSort::Name,
+                    MruSort::Name => MruSort::Recent,
+                };
+                let selected = self.selected_mru_path();
+                self.mru_changed(selected);
+            }
             _ => (),
         }
-        // eprintln!("{}", self.select_state.selected().unwrap_or(10));
+    }
+    fn selected_mru_path(&self) -> Option<String> {
+        let entry = self.selected().and_then(|i| self.config.mru.get(i))?;
+        Some(entry.path.clone())
+    }
+    // the mru list was changed from the start screen: put it back in order, keep the selection on
+    // the same diary, and save it.
+    fn mru_changed(&mut self, selected: Option<String>) {
+        self.status_msg.clear();
+        self.config.sort_mru();
+        let idx = selected.and_then(|path| self.config.mru.iter().position(|e| e.path == path));
+        self.select_state.select(idx);
+        self.refresh_recent();
+        if let Err(e) = self.config.store() {
+            self.set_status(e);
+        }
+    }
+    // open the selected mru entry on the edit screen.
+    fn open_selected(&mut self) {
+        if let Some(path) = self.selected_mru_path() {
+            if let Err(e) = self.open_path(&path) {
+                self.set_status(e);
+            }
+        }
+    }
+    // open a diary on the edit screen.  if it can't be read, say why and stay put.
+    pub fn open_path(&mut self, path: &str) -> Result<(), String> {
+        let path = canonicalize(path)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| path.to_string());
+        let mut diary = commit::Diary::read_from_path(&path)
+            .map_err(|e| format!("couldn't open {}: {}", path, e))?;
+        // the name inside is where it was last saved; the file may have moved since.
+        diary.name = path.clone();
+        self.file = diary;
+        self.remember(path);
+        self.unselect();
+        self.route_to(AppRoute::Edit);
+        Ok(())
+    }
+    // put a diary in the mru list, and have another look at what's in the list.
+    fn remember(&mut self, path: String) {
+        if let Err(e) = self.config.update_mru_with(path) {
+            self.set_status(e);
+        }
+        self.refresh_recent();
+    }
+    pub fn refresh_recent(&mut self) {
+        self.recent = self.config.mru.iter().map(|e| Summary::read(&e.path)).collect();
+    }
+    fn run_browse(&mut self, key: KeyEvent) {
+        let browser = match &mut self.browser {
+            Some(browser) => browser,
+            None => return,
+        };
+        match self.keymap.action(Context::Browse, &key) {
+            Some(Action::SelectUp) => browser.select_up(),
+            Some(Action::SelectDown) => browser.select_down(),
+            Some(Action::Parent) => browser.parent(),
+            Some(Action::Open) => self.browse_into(),
+            Some(Action::NewDiary) => {
+                let dir = browser.dir.to_string_lossy().into_owned();
+                self.routes.pop();
+                self.route_to(AppRoute::NewDiary);
+                self.new_diary = Some(NewDiaryForm::new(dir));
+            }
+            Some(Action::Help) => self.route_to(AppRoute::Help),
+            Some(Action::Leave) => {
+                self.routes.pop();
+                self.browser = None;
+            }
+            _ => (),
+        }
+    }
+    // go into the selected folder, or open the selected diary.
+    fn browse_into(&mut self) {
+        let file = self.browser.as_mut().and_then(|browser| browser.enter());
+        if let Some(path) = file {
+            match self.open_path(&path.to_string_lossy()) {
+                Ok(()) => self.browser = None,
+                Err(e) => self.set_status(e),
+            }
+        }
+    }
+    fn run_new_diary(&mut self, key: KeyEvent) {
+        let form = match &mut self.new_diary {
+            Some(form) => form,
+            None => return,
+        };
+        match self.keymap.action(Context::NewDiary, &key) {
+            None => {
+                if let Some(c) = typed_char(&key) {
+                    form.input().push(c);
+                    form.error = None;
+                }
+            }
+            Some(Action::Backspace) => {
+                form.input().pop();
+                form.error = None;
+            }
+            Some(Action::NextField) => {
+                form.field = match form.field {
+                    FormField::Name => FormField::Location,
+                    FormField::Location => FormField::Name,
+                };
+            }
+            // Enter on the name moves on to the location; on the location, it makes the diary.
+            Some(Action::Confirm) if form.field == FormField::Name => {
+                form.field = FormField::Location;
+            }
+            Some(Action::Confirm) => self.create_diary(),
+            Some(Action::Cancel) => {
+                self.routes.pop();
+                self.new_diary = None;
+            }
+            _ => (),
+        }
+    }
+    // make the diary the form describes, write it out straight away and start writing in it.
+    fn create_diary(&mut self) {
+        let created = match &self.new_diary {
+            Some(form) => form.check().and_then(|path| self.create_at(&path)),
+            None => return,
+        };
+        match created {
+            Ok(()) => self.new_diary = None,
+            Err(e) => {
+                if let Some(form) = &mut self.new_diary {
+                    form.error = Some(e);
+                }
+            }
+        }
+    }
+    // make an empty diary at `path`, write it out straight away and start writing in it.
+    pub fn create_at(&mut self, path: &Path) -> Result<(), String> {
+        let path = path.to_string_lossy().into_owned();
+        let diary = commit::Diary::from(path.clone(), Vec::new());
+        store::write(&diary, Path::new(&path))
+            .map_err(|e| format!("couldn't write {}: {}", path, e))?;
+        // now that it exists, its full path can be worked out.
+        let path = canonicalize(&path)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or(path);
+        self.file = commit::Diary::from(path.clone(), Vec::new());
+        self.remember(path);
+        self.unselect();
+        self.route_to(AppRoute::Edit);
+        self.change_mode(EditorMode::Writing);
+        Ok(())
+    }
+    // start editing the selected message, or write a new one if nothing is selected.
+    fn edit_selected(&mut self) {
+        if self.read_only {
+            return self.say_read_only();
+        }
+        if let Some(msg_idx) = self.selected() {
+            self.change_mode(EditorMode::Editing);
+
+            // so, msg_idx is usually the complement of the actual index in the
+            // file. e.g. msg_idx = 0? then the most recent message is chosen.
+            let msg_count = self.file.messages.len();
+            let file_idx = msg_count - msg_idx - 1;
+            // could also do iter().rev().nth(msg_idx)...
+
+            // take the message at file_idx, get its most recent commit,
+            let mrc = self
+                .file
+                .messages
+                .get(file_idx)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "shoulda had a message at mi {}, fi {}",
+                        msg_idx, file_idx
+                    )
+                })
+                .most_recent()
+                .expect("a message should have a commit... it's not possible to be without one")
+                .data();
+
+            // set the editing input bar to that and show it
+            self.edit = Some(Edit::from(mrc.to_string(), file_idx));
+        } else {
+            // he didn't select a message, so just put him in Write mode,
+            // on a new message.
+            self.change_mode(EditorMode::Writing);
+        }
     }
     fn run_edit(&mut self, key: KeyEvent) {
         match self.mode {
             EditorMode::Normal => {
-                match key.code {
-                    KeyCode::Char('i') => {
+                match self.keymap.action(Context::Normal, &key) {
+                    Some(Action::Insert | Action::Edit | Action::Save | Action::SaveAs)
+                        if self.read_only =>
+                    {
+                        self.say_read_only()
+                    }
+                    Some(Action::Insert) => {
                         self.change_mode(EditorMode::Writing);
                         self.unselect(); // leave message selection
                     }
-                    KeyCode::Char('e') => {
-                        if let Some(msg_idx) = self.selected() {
-                            self.change_mode(EditorMode::Editing);
-
-                            // so, msg_idx is usually the complement of the actual index in the
-                            // file. e.g. msg_idx = 0? then the most recent message is chosen.
-                            let msg_count = self.file.messages.len();
-                            let file_idx = msg_count - msg_idx - 1;
-                            // could also do iter().rev().nth(msg_idx)...
-
-                            // take the message at file_idx, get its most recent commit,
-                            let mrc = self
-                                .file
-                                .messages
-                                .iter()
-                                .nth(file_idx)
-                                .expect(
-                                    format!(
-                                        "shoulda had a message at mi {}, fi {}",
-                                        msg_idx, file_idx
-                                    )
-                                    .as_ref(),
-                                )
-                                .most_recent()
-                                .expect("a message should have a commit... it's not possible to be without one")
-                                .data();
-
-                            // set the editing input bar to that and show it
-                            self.edit = Some(Edit::from(mrc.to_string(), file_idx));
-                        } else {
-                            // he didn't select a message, so just put him in Write mode,
-                            // on a new message.
-                            self.change_mode(EditorMode::Writing);
-                        }
-                    }
-                    KeyCode::Char('W') => {
+                    Some(Action::Edit) => self.edit_selected(),
+                    Some(Action::SaveAs) => {
                         if !self.file.messages.is_empty() {
                             self.change_mode(EditorMode::Saving);
                         } else {
                             self.set_status(String::from("Nothing to write!..."));
                         }
                     }
-                    KeyCode::Char('w') => {
+                    Some(Action::Export) => {
+                        if self.file.messages.is_empty() {
+                            self.set_status(String::from("Nothing to export!..."));
+                        } else {
+                            self.temp_input = export_name(&self.file.name);
+                            self.change_mode(EditorMode::Exporting);
+                        }
+                    }
+                    Some(Action::Save) => {
                         // TODO: write file; give file name to write to; if no file name provided
                         // then prompt for a valid file name; else, give file's current file
                         // TODO make a whole saving screen, actually, since save-as will be useful
@@ -215,64 +626,114 @@ impl App {
                         // for now, if file is new, just save to file named after date of oldest message
                         if self.file.messages.is_empty() {
                             self.set_status(String::from("Nothing to write!..."));
+                        } else if self.file.name.is_empty() {
+                            self.change_mode(EditorMode::Saving);
                         } else {
-                            if self.file.name.is_empty() {
-                                self.change_mode(EditorMode::Saving);
-                            } else {
-                                // write to filename
-                                self.file.write_to_path(self.file.name.clone());
-                            }
+                            // write to filename
+                            self.save_file();
                         }
 
                         // write to mru when file has a filename and is being opened, or when
                         // a file is new and is being written.
                     }
-                    KeyCode::Char('h') => self.route_to(AppRoute::Help),
-                    KeyCode::Char('q') => self.route_to(AppRoute::PreQuit),
-                    KeyCode::Up => self.select_up(self.file.messages.len()),
-                    KeyCode::Down => self.select_down(self.file.messages.len()),
-                    KeyCode::Esc => self.unselect(),
+                    Some(Action::Help) => self.route_to(AppRoute::Help),
+                    Some(Action::Quit) => self.route_to(AppRoute::PreQuit),
+                    Some(Action::SelectUp) => self.select_up(self.file.messages.len()),
+                    Some(Action::SelectDown) => self.select_down(self.file.messages.len()),
+                    Some(Action::Unselect) => self.unselect(),
+                    Some(action) => {
+                        self.view.scroll(action);
+                    }
                     _ => {}
                 }
             }
             EditorMode::Writing => {
-                match key.code {
-                    KeyCode::Enter => {
-                        self.input.trim_end();
-                        self.file.push_string(self.input.to_string());
+                if self.config.keymap == KeymapProfile::Vi {
+                    match self.vi.handle(&mut self.input, &mut self.kill_ring, key) {
+                        ViOutcome::Handled => return,
+                        ViOutcome::Leave => {
+                            self.change_mode(EditorMode::Normal);
+                            return;
+                        }
+                        ViOutcome::Unhandled => (),
+                    }
+                }
+                let action = match self.keymap.action(Context::Writing, &key) {
+                    Some(action) => action,
+                    None => {
+                        if let Some(c) = typed_char(&key) {
+                            self.input.put(c);
+                        }
+                        return;
+                    }
+                };
+                if edit_input_action(&mut self.input, &mut self.kill_ring, &self.config, action) {
+                    return;
+                }
+                match action {
+                    Action::Commit => {
+                        let input = self.input.to_string();
+                        self.view.arrived(input.trim_end(), &self.theme);
+                        self.file.push_string(input.trim_end().to_string());
+                        // a committed message can't be undone from the input bar.
                         self.input.clear();
+                        self.vi.reset();
                     }
-                    KeyCode::Char(c) => {
-                        self.input.put(c);
+                    Action::Leave if self.input.selection().is_some() => {
+                        self.input.clear_selection();
                     }
-                    KeyCode::Left => self.input.left(),
-                    KeyCode::Right => self.input.right(),
-                    KeyCode::Backspace => self.input.back(),
-                    KeyCode::Delete => self.input.delete(),
-                    KeyCode::Esc | KeyCode::BackTab => {
+                    Action::Leave => {
                         self.change_mode(EditorMode::Normal);
                     }
-                    // TODO: implement cursor as well as inserting characters anywhere in the
-                    // input bar, not just at the end.
-                    _ => {}
+                    action => {
+                        self.view.scroll(action);
+                    }
                 }
             }
             EditorMode::Editing => {
                 if let Some(edit) = &mut self.edit {
-                    match key.code {
-                        KeyCode::Enter => {
-                            let input = edit.edit_input.trim_end();
+                    if self.config.keymap == KeymapProfile::Vi {
+                        match self.vi.handle(&mut edit.edit_input, &mut self.kill_ring, key) {
+                            ViOutcome::Handled => return,
+                            ViOutcome::Leave => {
+                                // same as leaving without vi: drop the edit.
+                                self.change_mode(EditorMode::Normal);
+                                self.edit = None;
+                                return;
+                            }
+                            ViOutcome::Unhandled => (),
+                        }
+                    }
+                    let action = match self.keymap.action(Context::Editing, &key) {
+                        Some(action) => action,
+                        None => {
+                            if let Some(c) = typed_char(&key) {
+                                edit.edit_input.put(c);
+                            }
+                            return;
+                        }
+                    };
+                    if edit_input_action(
+                        &mut edit.edit_input,
+                        &mut self.kill_ring,
+                        &self.config,
+                        action,
+                    ) {
+                        return;
+                    }
+                    match action {
+                        Action::Commit => {
+                            let input = edit.get();
+                            let input = input.trim_end();
 
                             // find the Message at edit.index and commit the current
                             // edit.input to it.
                             self.file
                                 .messages
-                                .iter_mut()
-                                .nth(edit.index)
-                                .expect(
-                                    format!("should have message at file index {}", edit.index)
-                                        .as_ref(),
-                                )
+                                .get_mut(edit.index)
+                                .unwrap_or_else(|| {
+                                    panic!("should have message at file index {}", edit.index)
+                                })
                                 .push_commit(commit::Commit::from_data(input.to_string()));
 
                             // now return to normal mode and unselect.
@@ -280,21 +741,18 @@ impl App {
                             self.edit = None;
                             self.unselect();
                         }
-                        KeyCode::Char(c) => {
-                            edit.edit_input.push(c);
-                        }
-                        KeyCode::Backspace => {
-                            edit.edit_input.pop();
+                        Action::Leave if edit.edit_input.selection().is_some() => {
+                            edit.edit_input.clear_selection();
                         }
-                        KeyCode::Esc | KeyCode::BackTab => {
-                            // clears the current edit state.  sorry, if you want to scroll
-                            // up, need mouse.
-                            // TODO: find a way to let people scroll while preserving the
-                            // edit state. i.e. without clearing self.edit outright.
+                        Action::Leave => {
+                            // clears the current edit state.  to look around without losing
+                            // the edit, scroll the view instead.
                             self.change_mode(EditorMode::Normal);
                             self.edit = None;
                         }
-                        _ => {}
+                        action => {
+                            self.view.scroll(action);
+                        }
                     }
                 } else {
                     // if we somehow are in editing mode without any information about what
@@ -303,32 +761,36 @@ impl App {
                     self.unselect();
                 }
             }
-            EditorMode::Saving => {
-                match key.code {
-                    KeyCode::Char(c) => self.temp_input.push(c),
-                    KeyCode::Backspace => {
+            EditorMode::Saving | EditorMode::Exporting => {
+                match self.keymap.action(Context::Saving, &key) {
+                    None => {
+                        if let Some(c) = typed_char(&key) {
+                            self.temp_input.push(c);
+                        }
+                    }
+                    Some(Action::Backspace) => {
                         self.temp_input.pop();
                     }
-                    KeyCode::Esc => self.change_mode(EditorMode::Normal),
-                    KeyCode::Enter => {
+                    Some(Action::Cancel) => self.change_mode(EditorMode::Normal),
+                    Some(Action::Confirm) if self.mode == EditorMode::Exporting => {
+                        self.change_mode(EditorMode::Normal);
+                        let name: String = self.temp_input.drain(..).collect();
+                        self.export_file(&name);
+                    }
+                    Some(Action::Confirm) => {
+                        self.change_mode(EditorMode::Normal);
                         if self.file.name.is_empty() {
                             if !self.file.messages.is_empty() {
                                 let name: String = self.temp_input.drain(..).collect();
-                                self.file.write_to_path(name.clone());
-                                self.file.name = name.clone();
-
-                                // save to mru
-                                // TODO: extract the filepath from this filename by expanding '.'
-                                // directory and then appending filename
-                                let filename = PathBuf::from(name.clone());
-                                self.config.update_mru_with(
-                                    canonicalize(&filename)
-                                        .expect("should've canonicalized the full path")
-                                        .into_os_string()
-                                        // .expect("Why wouldn't you be able to convert this to a string?...")
-                                        // .to_string(),
-                                        .into_string().expect("why wouldn't you be able to convert this into a string?")
-                                );
+                                if let Err(e) = store::write(&self.file, Path::new(&name)) {
+                                    return self.set_status(format!("couldn't save {}: {}", name, e));
+                                }
+                                // save to mru, by its full path now that it exists.
+                                let path = canonicalize(&name)
+                                    .map(|p| p.to_string_lossy().into_owned())
+                                    .unwrap_or(name);
+                                self.file.name = path.clone();
+                                self.remember(path);
                             } else {
                                 // if file is empty... don't write anything.
                                 self.set_status(String::from("Nothing to write!..."));
@@ -336,32 +798,236 @@ impl App {
                             }
                         } else {
                             // write to filename
-                            self.file.write_to_path(self.file.name.clone());
+                            self.save_file();
                         }
-                        self.change_mode(EditorMode::Normal);
                     }
                     _ => (),
                 }
             }
         }
     }
+    // write the diary out as markdown or html, going by the file's extension.  a web page gets
+    // the edit history too, since it can keep it folded away.
+    fn export_file(&mut self, name: &str) {
+        let path = expand_home(name.trim());
+        let format = match Format::from_path(&path) {
+            Some(format) => format,
+            None => return self.set_status(String::from("export to a .md, .html or .json file")),
+        };
+        let text = export::export(&self.file, format, format == Format::Html);
+        match fs::write(&path, text) {
+            Ok(()) => self.set_status(format!("exported to {}", path.display())),
+            Err(e) => self.set_status(format!("couldn't export to {}: {}", path.display(), e)),
+        }
+    }
+    // save the diary to its file, along with anything written to the file from elsewhere.
+    fn save_file(&mut self) {
+        match store::save(&mut self.file) {
+            Ok(0) => (),
+            Ok(added) => {
+                // the messages moved around under the selection.
+                self.unselect();
+                self.set_status(match added {
+                    1 => String::from("saved, along with 1 message added from outside"),
+                    n => format!("saved, along with {} messages added from outside", n),
+                });
+            }
+            Err(e) => self.set_status(format!("couldn't save {}: {}", self.file.name, e)),
+        }
+    }
+    // a bracketed paste arrives all at once, newlines included, so it goes straight into whatever
+    // is being typed into instead of being replayed as keypresses (where a newline would commit).
+    fn run_paste(&mut self, text: String) {
+        // a pasted name or folder goes into the new diary form, up to the first line break.
+        if let (Some(AppRoute::NewDiary), Some(form)) = (self.route(), &mut self.new_diary) {
+            if let Some(line) = text.lines().next() {
+                form.input().push_str(line);
+                form.error = None;
+            }
+            return;
+        }
+        if self.route() != Some(AppRoute::Edit) {
+            return;
+        }
+        if self.read_only {
+            return self.say_read_only();
+        }
+        // terminals usually send line breaks in a paste as carriage returns.
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        match self.mode {
+            EditorMode::Normal => {
+                // pasting into the message view starts a new message.
+                self.change_mode(EditorMode::Writing);
+                self.unselect();
+                self.input.insert_str(&text);
+            }
+            EditorMode::Writing => self.input.insert_str(&text),
+            EditorMode::Editing => {
+                if let Some(edit) = &mut self.edit {
+                    edit.edit_input.insert_str(&text);
+                }
+            }
+            EditorMode::Saving | EditorMode::Exporting => {
+                // file names don't have line breaks.
+                if let Some(line) = text.lines().next() {
+                    self.temp_input.push_str(line);
+                }
+            }
+        }
+    }
+    fn run_mouse(&mut self, mouse: MouseEvent) {
+        let (col, row) = (mouse.column, mouse.row);
+        match (self.route(), mouse.kind) {
+            (Some(AppRoute::Help), MouseEventKind::ScrollUp) => {
+                self.help.scroll = self.help.scroll.saturating_sub(3);
+            }
+            (Some(AppRoute::Help), MouseEventKind::ScrollDown) => self.help.scroll += 3,
+            // same as the arrow keys: the mru list is drawn top to bottom.
+            (Some(AppRoute::Start), MouseEventKind::ScrollUp) => {
+                self.select_down(self.config.mru.len())
+            }
+            (Some(AppRoute::Start), MouseEventKind::ScrollDown) => {
+                self.select_up(self.config.mru.len())
+            }
+            (Some(AppRoute::Start), MouseEventKind::Down(MouseButton::Left)) => {
+                if let Some(idx) = self.item_at(col, row) {
+                    let double = self.double_click(col, row);
+                    self.select_state.select(Some(idx));
+                    if double {
+                        self.open_selected();
+                    }
+                }
+            }
+            (Some(AppRoute::Browse), MouseEventKind::ScrollUp) => {
+                if let Some(browser) = &mut self.browser {
+                    browser.select_up();
+                }
+            }
+            (Some(AppRoute::Browse), MouseEventKind::ScrollDown) => {
+                if let Some(browser) = &mut self.browser {
+                    browser.select_down();
+                }
+            }
+            (Some(AppRoute::Browse), MouseEventKind::Down(MouseButton::Left)) => {
+                if let Some(idx) = self.item_at(col, row) {
+                    let double = self.double_click(col, row);
+                    if let Some(browser) = &mut self.browser {
+                        browser.selected = idx;
+                    }
+                    if double {
+                        self.browse_into();
+                    }
+                }
+            }
+            (Some(AppRoute::Edit), _) => self.run_edit_mouse(mouse),
+            _ => (),
+        }
+    }
+    fn run_edit_mouse(&mut self, mouse: MouseEvent) {
+        let (col, row) = (mouse.column, mouse.row);
+        if matches!(self.mode, EditorMode::Saving | EditorMode::Exporting) {
+            return;
+        }
+        match mouse.kind {
+            // the wheel scrolls the view and leaves the selection and the input bar alone.
+            MouseEventKind::ScrollUp => self.view.scroll += 3,
+            MouseEventKind::ScrollDown => self.view.scroll = self.view.scroll.saturating_sub(3),
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(idx) = self.item_at(col, row) {
+                    let double = self.double_click(col, row);
+                    // clicking a message leaves the input bar.  whatever was being written
+                    // stays there; an edit in progress is dropped, like leaving it with Esc.
+                    if self.mode != EditorMode::Normal {
+                        self.change_mode(EditorMode::Normal);
+                        self.edit = None;
+                    }
+                    self.select_state.select(Some(idx));
+                    if double {
+                        self.edit_selected();
+                    }
+                } else if contains(self.mouse.input, col, row) && self.read_only {
+                    self.say_read_only();
+                } else if contains(self.mouse.input, col, row) {
+                    if self.mode == EditorMode::Normal {
+                        self.change_mode(EditorMode::Writing);
+                        self.unselect();
+                    }
+                    // past the end of a line puts the cursor at its end; below the text, at the
+                    // end of everything.
+                    let line = (row - self.mouse.input.y) as usize;
+                    let place = match self.mouse.input_lines.get(line) {
+                        Some((start, text)) => {
+                            start + grapheme::at_column(text, (col - self.mouse.input.x) as usize)
+                        }
+                        None => usize::MAX,
+                    };
+                    match (&self.mode, &mut self.edit) {
+                        (EditorMode::Editing, Some(edit)) => edit.edit_input.seek(place),
+                        _ => self.input.seek(place),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    // the list item drawn under a screen cell, if any.
+    fn item_at(&self, col: u16, row: u16) -> Option<usize> {
+        if !contains(self.mouse.list, col, row) {
+            return None;
+        }
+        self.mouse
+            .rows
+            .iter()
+            .find(|(top, bottom, _)| (*top..*bottom).contains(&row))
+            .map(|(_, _, idx)| *idx)
+    }
+    // note a left click, and say whether it finished a double click.
+    fn double_click(&mut self, col: u16, row: u16) -> bool {
+        let now = Instant::now();
+        let double = matches!(self.last_click,
+            Some((at, c, r)) if c == col && r == row && now.duration_since(at) < DOUBLE_CLICK);
+        // a third click starts over instead of counting as another double click.
+        self.last_click = if double { None } else { Some((now, col, row)) };
+        double
+    }
     fn run_help(&mut self, key: KeyEvent) {
-        match key.code {
-            // KeyCode::Char(c) => {
-            //     self.routes.pop();
-            // }
-            _ => {
+        if self.help.searching {
+            // the search box is a plain text field, like the file name prompt.
+            match key.code {
+                KeyCode::Enter => self.help.searching = false,
+                KeyCode::Esc => {
+                    self.help.searching = false;
+                    self.help.search.clear();
+                }
+                KeyCode::Backspace => {
+                    self.help.search.pop();
+                }
+                _ => {
+                    if let Some(c) = typed_char(&key) {
+                        self.help.search.push(c);
+                        self.help.scroll = 0;
+                    }
+                }
+            }
+            return;
+        }
+        match self.keymap.action(Context::Help, &key) {
+            Some(Action::SelectUp) => self.help.scroll = self.help.scroll.saturating_sub(1),
+            Some(Action::SelectDown) => self.help.scroll += 1,
+            Some(Action::Search) => self.help.searching = true,
+            // any key that isn't bound to something else leaves the help screen.
+            Some(Action::Leave) | None => {
                 self.routes.pop();
             }
+            _ => (),
         }
     }
     fn run_prequit(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Char('n') => {
+        match self.keymap.action(Context::PreQuit, &key) {
+            Some(Action::Cancel) => {
                 self.routes.pop();
-            },
-            KeyCode::Char('y') => self.quit(),
-            KeyCode::Enter => self.quit(),
+            }
+            Some(Action::Confirm) => self.quit(),
             _ => (),
         }
     }
@@ -372,12 +1038,22 @@ impl App {
                 self.routes.clear();
                 self.routes.push(AppRoute::Start);
             }
+            AppRoute::Browse => {
+                self.browser = Some(Browser::open(self.browse_dir()));
+                self.routes.push(AppRoute::Browse);
+            }
+            AppRoute::NewDiary => {
+                let dir = self.browse_dir().to_string_lossy().into_owned();
+                self.new_diary = Some(NewDiaryForm::new(dir));
+                self.routes.push(AppRoute::NewDiary);
+            }
             AppRoute::Edit => {
                 // if we enter edit from start, help, prequit, we don't need to go back.
                 self.routes.clear();
                 self.routes.push(AppRoute::Edit);
             }
             AppRoute::Help => {
+                self.help = HelpState::default();
                 self.routes.push(AppRoute::Help);
             }
             AppRoute::PreQuit => {
@@ -385,10 +1061,26 @@ impl App {
             }
         }
     }
+    // where to start looking for diaries: next to the newest one in the mru list, or here.
+    fn browse_dir(&self) -> PathBuf {
+        self.config
+            .last_opened()
+            .and_then(|e| PathBuf::from(&e.path).parent().map(|dir| dir.to_path_buf()))
+            .filter(|dir| dir.is_dir())
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
     fn change_mode(&mut self, mode: EditorMode) {
         self.status_msg = String::new();
+        if mode == EditorMode::Writing || mode == EditorMode::Editing {
+            // every trip into the input bar starts in vi's insert mode.
+            self.vi.reset();
+        }
         self.mode = mode;
     }
+    fn say_read_only(&mut self) {
+        self.set_status(String::from("this diary is open read-only"));
+    }
     fn set_status(&mut self, msg: String) {
         self.status_msg = msg;
     }
@@ -397,6 +1089,7 @@ impl App {
         self.select_state.select(None);
     }
     fn select_up(&mut self, len: usize) {
+        self.view.reveal = true;
         let idx = match self.select_state.selected() {
             Some(i) => {
                 if i >= len - 1 {
@@ -417,6 +1110,7 @@ impl App {
         self.select_state.select(idx);
     }
     fn select_down(&mut self, len: usize) {
+        self.view.reveal = true;
         let idx = match self.select_state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -442,24 +1136,70 @@ impl App {
 
 impl Default for App {
     fn default() -> App {
-        // TODO locate mru.  if mru not found, then default.
-        let config: DiaryConfig = if let Ok(config) = confy::load(crate::util::APP_NAME, None) {
-            config
-        } else {
-            DiaryConfig::default()
+        App::with_config(None)
+    }
+}
+
+impl App {
+    // the app, set up from the config at `config_path`, or the usual one.
+    pub fn with_config(config_path: Option<PathBuf>) -> App {
+        let mut warnings = Vec::new();
+        let config = match DiaryConfig::load(config_path.clone()) {
+            Ok(config) => config,
+            Err(e) => {
+                warnings.push(format!(
+                    "config: {}; using the defaults, and leaving the file alone",
+                    e
+                ));
+                DiaryConfig::unreadable(config_path)
+            }
+        };
+        App::from_config(config, warnings)
+    }
+    // the app, set up from a config that's already loaded.  `warnings` are about the loading.
+    pub fn from_config(mut config: DiaryConfig, mut warnings: Vec<String>) -> App {
+        config.sort_mru();
+        if config.prune_missing && config.prune_mru() > 0 {
+            if let Err(e) = config.store() {
+                warnings.push(e);
+            }
+        }
+        let keymap = Keymap::load(config.keymap, &config.keys);
+        warnings.extend(keymap.warnings.iter().cloned());
+        let theme = Theme::load(&config.theme, theme::no_color());
+        warnings.extend(theme.warnings.iter().cloned());
+        // problems with the config go in the message bar, where they'll actually be seen.
+        let status_msg = match warnings.len() {
+            0 => String::default(),
+            1 => warnings[0].clone(),
+            n => format!("{} (and {} more)", warnings[0], n - 1),
         };
-        let app = App {
+        let mut app = App {
             config,
+            keymap,
+            theme,
             routes: vec![AppRoute::Start],
             file: commit::Diary::new(),
-            input: GapBuffer::default(),
+            input: EditArea::new(),
             edit: None,
+            kill_ring: KillRing::new(),
+            vi: ViState::new(),
             mode: EditorMode::Normal,
-            status_msg: String::default(),
+            status_msg,
             temp_input: String::default(),
+            help: HelpState::default(),
             select_state: ListState::default(),
+            list_offset: 0,
+            read_only: false,
+            recent: Vec::new(),
+            browser: None,
+            new_diary: None,
+            view: MessageView::default(),
+            mouse: MouseTargets::default(),
+            too_small: false,
+            last_click: None,
         };
-
+        app.refresh_recent();
         app
     }
 }
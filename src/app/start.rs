@@ -0,0 +1,219 @@
+// What the start screen needs besides the mru list itself: a look inside each recent diary, the
+// file browser, and the new diary form.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::prelude::{DateTime, Utc};
+
+use crate::commit::Diary;
+
+// the file extension diaries are saved with.
+pub const DIARY_EXTENSION: &str = "diary";
+
+// A recent diary, as shown on the start screen.
+pub struct Summary {
+    pub messages: usize,
+    // when the last message was written or edited.
+    pub modified: Option<DateTime<Utc>>,
+    // the first line of the newest message.
+    pub last: Option<String>,
+}
+
+impl Summary {
+    pub fn read(path: &str) -> Result<Summary, String> {
+        if !Path::new(path).exists() {
+            return Err(String::from("missing"));
+        }
+        let diary = Diary::read_from_path(path).map_err(|e| e.to_string())?;
+        Ok(Summary {
+            messages: diary.messages.len(),
+            modified: diary.messages.iter().filter_map(|m| m.modified()).max(),
+            last: diary
+                .messages
+                .last()
+                .and_then(|m| m.most_recent())
+                .map(|c| c.data().lines().next().unwrap_or("").to_string()),
+        })
+    }
+}
+
+// what a diary is called: its file name, without the extension.
+pub fn diary_title(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map_or_else(|| path.to_string(), |stem| stem.to_string_lossy().into_owned())
+}
+
+// "~/notes" is the home directory's notes.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+pub struct Entry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+// A directory listing that only shows folders and diaries.
+pub struct Browser {
+    pub dir: PathBuf,
+    pub entries: Vec<Entry>,
+    pub selected: usize,
+    // the first entry on screen; the screen keeps it up to date.
+    pub offset: usize,
+    // why the directory couldn't be listed, if it couldn't.
+    pub error: Option<String>,
+}
+
+impl Browser {
+    pub fn open(dir: PathBuf) -> Self {
+        let mut browser = Browser {
+            dir: fs::canonicalize(&dir).unwrap_or(dir),
+            entries: Vec::new(),
+            selected: 0,
+            offset: 0,
+            error: None,
+        };
+        browser.read();
+        browser
+    }
+    fn read(&mut self) {
+        self.entries.clear();
+        self.selected = 0;
+        self.offset = 0;
+        self.error = None;
+        if let Some(parent) = self.dir.parent() {
+            self.entries.push(Entry {
+                name: String::from(".."),
+                path: parent.to_path_buf(),
+                is_dir: true,
+            });
+        }
+        let listing = match fs::read_dir(&self.dir) {
+            Ok(listing) => listing,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        let mut dirs = Vec::new();
+        let mut diaries = Vec::new();
+        for entry in listing.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // hidden files stay hidden.
+            if name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(Entry { name, path, is_dir: true });
+            } else if path.extension().is_some_and(|ext| ext == DIARY_EXTENSION) {
+                diaries.push(Entry { name, path, is_dir: false });
+            }
+        }
+        dirs.sort_by_key(|e| e.name.to_lowercase());
+        diaries.sort_by_key(|e| e.name.to_lowercase());
+        self.entries.extend(dirs);
+        self.entries.extend(diaries);
+    }
+    pub fn select_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+    pub fn select_down(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+    pub fn parent(&mut self) {
+        if let Some(parent) = self.dir.parent().map(Path::to_path_buf) {
+            let child = std::mem::replace(&mut self.dir, parent);
+            self.read();
+            // land on the directory we just came out of.
+            if let Some(i) = self.entries.iter().position(|e| e.path == child) {
+                self.selected = i;
+            }
+        }
+    }
+    // go into the selected directory, or hand back the selected diary to open.
+    pub fn enter(&mut self) -> Option<PathBuf> {
+        let entry = self.entries.get(self.selected)?;
+        if entry.is_dir {
+            let path = entry.path.clone();
+            if entry.name == ".." {
+                self.parent();
+            } else {
+                self.dir = path;
+                self.read();
+            }
+            None
+        } else {
+            Some(entry.path.clone())
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum FormField {
+    Name,
+    Location,
+}
+
+// The new diary form: what it's called and which folder it goes in.
+pub struct NewDiaryForm {
+    pub name: String,
+    pub location: String,
+    pub field: FormField,
+    pub error: Option<String>,
+}
+
+impl NewDiaryForm {
+    pub fn new(location: String) -> Self {
+        NewDiaryForm {
+            name: String::new(),
+            location,
+            field: FormField::Name,
+            error: None,
+        }
+    }
+    pub fn input(&mut self) -> &mut String {
+        match self.field {
+            FormField::Name => &mut self.name,
+            FormField::Location => &mut self.location,
+        }
+    }
+    // where the diary will be saved.
+    pub fn path(&self) -> PathBuf {
+        let name = self.name.trim();
+        let file = if name.ends_with(&format!(".{}", DIARY_EXTENSION)) {
+            name.to_string()
+        } else {
+            format!("{}.{}", name, DIARY_EXTENSION)
+        };
+        expand_home(self.location.trim()).join(file)
+    }
+    // make sure the diary can be created; the reason it can't, otherwise.
+    pub fn check(&self) -> Result<PathBuf, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(String::from("the diary needs a name"));
+        }
+        if name.contains('/') {
+            return Err(String::from("the name can't have a / in it"));
+        }
+        let dir = expand_home(self.location.trim());
+        if !dir.is_dir() {
+            return Err(format!("there's no folder at {}", dir.display()));
+        }
+        let path = self.path();
+        if path.exists() {
+            return Err(format!("{} already exists", path.display()));
+        }
+        Ok(path)
+    }
+}
@@ -0,0 +1,497 @@
+// The command line.  There aren't enough options to be worth pulling in a parser for.
+use std::path::PathBuf;
+
+use crate::export::Format;
+use crate::import::Source;
+
+pub const USAGE: &str = "\
+usage: elephant-diary [OPTIONS] [DIARY]
+       elephant-diary [--config PATH] add [--diary PATH] [TEXT...]
+       elephant-diary [--config PATH] cat [--diary PATH] [--json]
+       elephant-diary [--config PATH] tail [-n N] [--follow] [--diary PATH] [--json]
+       elephant-diary [--config PATH] grep PATTERN [--history] [-i] [--diary PATH] [--json]
+       elephant-diary [--config PATH] export [--format md|html|json|jsonl] [--history] [-o FILE] [--diary PATH]
+       elephant-diary [--config PATH] import FILE [--format FORMAT] [--author NAME]... [--dry-run] [--diary PATH]
+       elephant-diary [--config PATH] merge OTHER [-o FILE] [--dry-run] [--diary PATH]
+
+Opens DIARY on the edit screen, or the start screen if there isn't one.
+
+options:
+  --new NAME        make a new diary called NAME (a path, or a name for one in this folder)
+  --config PATH     use the config file at PATH instead of the usual one
+  --read-only       look, but don't write anything in the diary
+  -h, --help        show this and quit
+  -V, --version     show the version and quit
+
+commands:
+  add               write a message without opening the app.  the text is TEXT, or whatever
+                    comes in on stdin; the diary is the last one opened unless --diary says
+  cat               print every message, with the time it was written
+  tail              print the last N messages (10 if not given); --follow keeps printing new
+                    ones as they're written
+  grep              print the messages matching the regex PATTERN; --history looks through
+                    every version of each message, -i ignores case
+  --json            prints a JSON object per message, one a line, instead of text
+  export            write the diary out as Markdown or a web page, grouped by day.  the format
+                    comes from --format or the -o file's extension; --history puts each edited
+                    message's earlier versions in the page.  json and jsonl keep everything,
+                    every version of every message, and can be read back in with import
+  import            add the messages in FILE to the diary, skipping any it already has.  a
+                    diary that doesn't exist yet is made.  FILE can be one of export's json
+                    files, a text or Markdown journal with a heading for each day, a Day One
+                    or Journey export, or a Discord channel saved by DiscordChatExporter as
+                    JSON, where --author picks whose messages to take (by name, nickname or
+                    id).  --dry-run says what would be imported without importing it
+  merge             put the messages of the diary OTHER into the diary, in the order they were
+                    written.  a message edited differently in each keeps every version from
+                    both, and is listed.  -o writes the two together to a new FILE instead";
+
+// What was asked for on the command line.
+pub enum Command {
+    Tui(TuiArgs),
+    Add(AddArgs),
+    Read(ReadArgs),
+    Export(ExportArgs),
+    Import(ImportArgs),
+    Merge(MergeArgs),
+    Help,
+    Version,
+}
+
+#[derive(Default)]
+pub struct TuiArgs {
+    pub diary: Option<PathBuf>,
+    pub new: Option<String>,
+    pub config: Option<PathBuf>,
+    pub read_only: bool,
+}
+
+pub struct AddArgs {
+    pub config: Option<PathBuf>,
+    pub diary: Option<PathBuf>,
+    // the words of the message; empty means read it from stdin.
+    pub text: Vec<String>,
+}
+
+// cat, tail and grep: the ones that print a diary.
+pub struct ReadArgs {
+    pub config: Option<PathBuf>,
+    pub diary: Option<PathBuf>,
+    pub json: bool,
+    pub what: Read,
+}
+
+pub enum Read {
+    Cat,
+    Tail { lines: usize, follow: bool },
+    Grep { pattern: String, history: bool, ignore_case: bool },
+}
+
+pub struct ExportArgs {
+    pub config: Option<PathBuf>,
+    pub diary: Option<PathBuf>,
+    pub format: Option<Format>,
+    pub history: bool,
+    // stdout if there isn't one.
+    pub output: Option<PathBuf>,
+}
+
+pub struct ImportArgs {
+    pub config: Option<PathBuf>,
+    // the diary to add to.
+    pub diary: Option<PathBuf>,
+    // what's being imported.
+    pub file: PathBuf,
+    pub source: Option<Source>,
+    // for chat logs: whose messages to take.
+    pub authors: Vec<String>,
+    // just say what would be imported.
+    pub dry_run: bool,
+}
+
+pub struct MergeArgs {
+    pub config: Option<PathBuf>,
+    // the diary merged into.
+    pub diary: Option<PathBuf>,
+    pub other: PathBuf,
+    // where to write the merged diary, if not over the diary.
+    pub output: Option<PathBuf>,
+    pub dry_run: bool,
+}
+
+// The arguments, one at a time, with "--flag=value" split in two.
+struct Args<I: Iterator<Item = String>> {
+    args: I,
+    // the last flag and the value after its '=', until the value's taken.
+    value: Option<(String, String)>,
+}
+
+impl<I: Iterator<Item = String>> Args<I> {
+    fn next(&mut self) -> Result<Option<String>, String> {
+        if let Some((flag, _)) = self.value.take() {
+            return Err(format!("{} doesn't take a value", flag));
+        }
+        let arg = match self.args.next() {
+            Some(arg) => arg,
+            None => return Ok(None),
+        };
+        match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                self.value = Some((flag.to_string(), value.to_string()));
+                Ok(Some(flag.to_string()))
+            }
+            _ => Ok(Some(arg)),
+        }
+    }
+    // the value of a flag: after its '=', or the next argument.
+    fn value(&mut self, flag: &str) -> Result<String, String> {
+        self.value
+            .take()
+            .map(|(_, value)| value)
+            .or_else(|| self.args.next())
+            .ok_or_else(|| format!("{} needs a value", flag))
+    }
+    fn rest(&mut self) -> Vec<String> {
+        self.args.by_ref().collect()
+    }
+}
+
+// read the arguments, not counting the program's name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = Args {
+        args: args.into_iter(),
+        value: None,
+    };
+    let mut tui = TuiArgs::default();
+    // only --config can come before a command.
+    let mut only_config = true;
+    while let Some(arg) = args.next()? {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--config" => tui.config = Some(PathBuf::from(args.value("--config")?)),
+            "--new" => tui.new = Some(args.value("--new")?),
+            "--read-only" => tui.read_only = true,
+            // a diary called "add" (or "cat", ...) can still be opened as ./add.
+            "add" if only_config && tui.diary.is_none() => return parse_add(args, tui.config),
+            "cat" | "tail" | "grep" if only_config && tui.diary.is_none() => {
+                return parse_read(&arg, args, tui.config)
+            }
+            "export" if only_config && tui.diary.is_none() => {
+                return parse_export(args, tui.config)
+            }
+            "import" if only_config && tui.diary.is_none() => {
+                return parse_import(args, tui.config)
+            }
+            "merge" if only_config && tui.diary.is_none() => {
+                return parse_merge(args, tui.config)
+            }
+            s if s.starts_with('-') && s != "-" => return Err(format!("unknown option {}", s)),
+            _ => {
+                if tui.diary.is_some() {
+                    return Err(String::from("only one diary can be opened at a time"));
+                }
+                tui.diary = Some(PathBuf::from(arg));
+            }
+        }
+        only_config = tui.new.is_none() && !tui.read_only;
+    }
+    if tui.new.is_some() && tui.diary.is_some() {
+        return Err(String::from("--new makes a diary to open; don't give another one too"));
+    }
+    if tui.new.is_some() && tui.read_only {
+        return Err(String::from("a new diary can't be read-only"));
+    }
+    Ok(Command::Tui(tui))
+}
+
+fn parse_add<I: Iterator<Item = String>>(
+    mut args: Args<I>,
+    config: Option<PathBuf>,
+) -> Result<Command, String> {
+    let mut add = AddArgs {
+        config,
+        diary: None,
+        text: Vec::new(),
+    };
+    while let Some(arg) = args.next()? {
+        match arg.as_str() {
+            "--diary" => add.diary = Some(PathBuf::from(args.value("--diary")?)),
+            "--config" => add.config = Some(PathBuf::from(args.value("--config")?)),
+            // everything after -- is text, even if it looks like an option.
+            "--" => add.text.extend(args.rest()),
+            s if s.starts_with("--") => return Err(format!("unknown option {} for add", s)),
+            _ => add.text.push(arg),
+        }
+    }
+    Ok(Command::Add(add))
+}
+
+fn parse_read<I: Iterator<Item = String>>(
+    command: &str,
+    mut args: Args<I>,
+    config: Option<PathBuf>,
+) -> Result<Command, String> {
+    let mut read = ReadArgs {
+        config,
+        diary: None,
+        json: false,
+        what: match command {
+            "cat" => Read::Cat,
+            "tail" => Read::Tail {
+                lines: 10,
+                follow: false,
+            },
+            _ => Read::Grep {
+                pattern: String::new(),
+                history: false,
+                ignore_case: false,
+            },
+        },
+    };
+    let mut pattern = None;
+    while let Some(arg) = args.next()? {
+        match (arg.as_str(), &mut read.what) {
+            ("--diary", _) => read.diary = Some(PathBuf::from(args.value("--diary")?)),
+            ("--config", _) => read.config = Some(PathBuf::from(args.value("--config")?)),
+            ("--json", _) => read.json = true,
+            ("-n" | "--lines", Read::Tail { lines, .. }) => {
+                *lines = parse_count(&args.value(&arg)?)?;
+            }
+            // -n5, like tail's.
+            (s, Read::Tail { lines, .. }) if s.starts_with("-n") && s.len() > 2 => {
+                *lines = parse_count(&s[2..])?;
+            }
+            ("-f" | "--follow", Read::Tail { follow, .. }) => *follow = true,
+            ("--history", Read::Grep { history, .. }) => *history = true,
+            ("-i" | "--ignore-case", Read::Grep { ignore_case, .. }) => *ignore_case = true,
+            // a pattern that starts with '-' goes after --.
+            ("--", Read::Grep { .. }) if pattern.is_none() => {
+                let mut rest = args.rest().into_iter();
+                pattern = rest.next();
+                if let Some(extra) = rest.next() {
+                    return Err(format!("grep doesn't know what to do with {}", extra));
+                }
+            }
+            (s, Read::Grep { .. }) if pattern.is_none() && !s.starts_with('-') => {
+                pattern = Some(arg);
+            }
+            (s, _) if s.starts_with('-') => {
+                return Err(format!("unknown option {} for {}", s, command));
+            }
+            _ => return Err(format!("{} doesn't know what to do with {}", command, arg)),
+        }
+    }
+    if let Read::Grep { pattern: p, .. } = &mut read.what {
+        *p = pattern.ok_or("grep needs a pattern to look for")?;
+    }
+    Ok(Command::Read(read))
+}
+
+fn parse_export<I: Iterator<Item = String>>(
+    mut args: Args<I>,
+    config: Option<PathBuf>,
+) -> Result<Command, String> {
+    let mut export = ExportArgs {
+        config,
+        diary: None,
+        format: None,
+        history: false,
+        output: None,
+    };
+    while let Some(arg) = args.next()? {
+        match arg.as_str() {
+            "--diary" => export.diary = Some(PathBuf::from(args.value("--diary")?)),
+            "--config" => export.config = Some(PathBuf::from(args.value("--config")?)),
+            "--format" => {
+                let name = args.value("--format")?;
+                export.format = Some(
+                    Format::from_name(&name)
+                        .ok_or_else(|| format!("\"{}\" isn't a format; try md, html, json or jsonl", name))?,
+                );
+            }
+            "--history" => export.history = true,
+            "-o" | "--output" => export.output = Some(PathBuf::from(args.value("--output")?)),
+            _ => return Err(format!("export doesn't know what to do with {}", arg)),
+        }
+    }
+    Ok(Command::Export(export))
+}
+
+fn parse_import<I: Iterator<Item = String>>(
+    mut args: Args<I>,
+    config: Option<PathBuf>,
+) -> Result<Command, String> {
+    let mut diary = None;
+    let mut config = config;
+    let mut file = None;
+    let mut source = None;
+    let mut authors = Vec::new();
+    let mut dry_run = false;
+    while let Some(arg) = args.next()? {
+        match arg.as_str() {
+            "--diary" => diary = Some(PathBuf::from(args.value("--diary")?)),
+            "--config" => config = Some(PathBuf::from(args.value("--config")?)),
+            "--format" => {
+                let name = args.value("--format")?;
+                source = Some(
+                    Source::from_name(&name)
+                        .ok_or_else(|| format!("can't import \"{}\"; try json, jsonl, text, dayone, journey or discord", name))?,
+                );
+            }
+            "--author" => authors.push(args.value("--author")?),
+            "-n" | "--dry-run" => dry_run = true,
+            s if s.starts_with('-') => return Err(format!("unknown option {} for import", s)),
+            _ if file.is_none() => file = Some(PathBuf::from(arg)),
+            _ => return Err(String::from("import takes one file at a time")),
+        }
+    }
+    Ok(Command::Import(ImportArgs {
+        config,
+        diary,
+        file: file.ok_or("import needs a file to import")?,
+        source,
+        authors,
+        dry_run,
+    }))
+}
+
+fn parse_merge<I: Iterator<Item = String>>(
+    mut args: Args<I>,
+    config: Option<PathBuf>,
+) -> Result<Command, String> {
+    let mut config = config;
+    let mut diary = None;
+    let mut other = None;
+    let mut output = None;
+    let mut dry_run = false;
+    while let Some(arg) = args.next()? {
+        match arg.as_str() {
+            "--diary" => diary = Some(PathBuf::from(args.value("--diary")?)),
+            "--config" => config = Some(PathBuf::from(args.value("--config")?)),
+            "-o" | "--output" => output = Some(PathBuf::from(args.value("--output")?)),
+            "-n" | "--dry-run" => dry_run = true,
+            s if s.starts_with('-') => return Err(format!("unknown option {} for merge", s)),
+            _ if other.is_none() => other = Some(PathBuf::from(arg)),
+            _ => return Err(String::from("merge takes one diary at a time")),
+        }
+    }
+    Ok(Command::Merge(MergeArgs {
+        config,
+        diary,
+        other: other.ok_or("merge needs a diary to merge in")?,
+        output,
+        dry_run,
+    }))
+}
+
+fn parse_count(s: &str) -> Result<usize, String> {
+    s.parse()
+        .map_err(|_| format!("\"{}\" isn't a number of messages", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Command, String> {
+        parse(args.split_whitespace().map(String::from))
+    }
+
+    fn read(args: &str) -> ReadArgs {
+        match parse_str(args) {
+            Ok(Command::Read(read)) => read,
+            Ok(_) => panic!("{} isn't cat, tail or grep", args),
+            Err(e) => panic!("{}: {}", args, e),
+        }
+    }
+
+    fn error(args: &str) -> String {
+        match parse_str(args) {
+            Err(e) => e,
+            Ok(_) => panic!("{} should have been an error", args),
+        }
+    }
+
+    #[test]
+    fn cat_takes_a_diary_and_json() {
+        let cat = read("--config c.toml cat --diary d.diary --json");
+        assert!(matches!(cat.what, Read::Cat));
+        assert_eq!(cat.config, Some(PathBuf::from("c.toml")));
+        assert_eq!(cat.diary, Some(PathBuf::from("d.diary")));
+        assert!(cat.json);
+        let cat = read("cat --diary=d.diary");
+        assert_eq!(cat.diary, Some(PathBuf::from("d.diary")));
+        assert!(!cat.json);
+    }
+
+    #[test]
+    fn tail_counts() {
+        let lines = |args| match read(args).what {
+            Read::Tail { lines, follow } => (lines, follow),
+            _ => panic!("{} isn't tail", args),
+        };
+        assert_eq!(lines("tail"), (10, false));
+        assert_eq!(lines("tail -n 5"), (5, false));
+        assert_eq!(lines("tail -n5 -f"), (5, true));
+        assert_eq!(lines("tail --lines=3 --follow"), (3, true));
+        assert_eq!(lines("tail --lines 0"), (0, false));
+    }
+
+    #[test]
+    fn tail_needs_a_number() {
+        assert_eq!(error("tail -n x"), "\"x\" isn't a number of messages");
+        assert_eq!(error("tail -nx"), "\"x\" isn't a number of messages");
+        assert_eq!(error("tail -n -5"), "\"-5\" isn't a number of messages");
+        assert_eq!(error("tail --lines=many"), "\"many\" isn't a number of messages");
+        assert_eq!(error("tail -n"), "-n needs a value");
+        assert_eq!(error("tail --lines"), "--lines needs a value");
+        assert_eq!(error("tail --diary"), "--diary needs a value");
+    }
+
+    #[test]
+    fn grep_takes_a_pattern_and_its_flags() {
+        let grep = |args| match read(args).what {
+            Read::Grep {
+                pattern,
+                history,
+                ignore_case,
+            } => (pattern, history, ignore_case),
+            _ => panic!("{} isn't grep", args),
+        };
+        assert_eq!(grep("grep tea"), (String::from("tea"), false, false));
+        assert_eq!(grep("grep -i tea --history"), (String::from("tea"), true, true));
+        assert_eq!(grep("grep --ignore-case -- -tea"), (String::from("-tea"), false, true));
+        assert_eq!(error("grep"), "grep needs a pattern to look for");
+        assert_eq!(error("grep -i"), "grep needs a pattern to look for");
+        assert_eq!(error("grep tea coffee"), "grep doesn't know what to do with coffee");
+        assert_eq!(error("grep -- tea coffee"), "grep doesn't know what to do with coffee");
+    }
+
+    #[test]
+    fn flags_only_go_with_their_commands() {
+        assert_eq!(error("cat -n 5"), "unknown option -n for cat");
+        assert_eq!(error("cat --follow"), "unknown option --follow for cat");
+        assert_eq!(error("tail -i"), "unknown option -i for tail");
+        assert_eq!(error("tail --history"), "unknown option --history for tail");
+        assert_eq!(error("grep tea -f"), "unknown option -f for grep");
+        assert_eq!(error("cat --jsn"), "unknown option --jsn for cat");
+        assert_eq!(error("cat extra"), "cat doesn't know what to do with extra");
+        assert_eq!(error("tail 5"), "tail doesn't know what to do with 5");
+    }
+
+    #[test]
+    fn switches_dont_take_values() {
+        assert_eq!(error("cat --json=yes"), "--json doesn't take a value");
+        assert_eq!(error("tail --follow=1"), "--follow doesn't take a value");
+    }
+
+    #[test]
+    fn unknown_options_before_a_command() {
+        assert_eq!(error("--jsn cat"), "unknown option --jsn");
+        assert_eq!(error("--config"), "--config needs a value");
+        // a command after a diary is a second diary.
+        assert_eq!(error("d.diary cat"), "only one diary can be opened at a time");
+        assert!(matches!(parse_str("--help cat"), Ok(Command::Help)));
+    }
+}
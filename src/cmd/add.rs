@@ -0,0 +1,37 @@
+// `elephant-diary add`: a one line message from the shell, straight into a diary.
+use std::io::{self, IsTerminal, Read};
+
+use crate::cli::AddArgs;
+use crate::store;
+
+pub fn run(args: AddArgs) -> Result<(), String> {
+    let path = match args.diary {
+        Some(path) => path,
+        None => super::last_opened(args.config)?,
+    };
+    if !path.exists() {
+        return Err(format!("{} doesn't exist", path.display()));
+    }
+
+    let text = if args.text.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("type the message, then Ctrl-D on a line of its own");
+        }
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("couldn't read the message: {}", e))?;
+        text
+    } else {
+        args.text.join(" ")
+    };
+    // the newline `echo` ends with isn't part of the message.
+    let text = text.replace("\r\n", "\n");
+    let text = text.trim_end_matches('\n');
+    if text.trim().is_empty() {
+        return Err(String::from("there's nothing to add"));
+    }
+
+    store::append(&path, text.to_string())
+        .map_err(|e| format!("couldn't add to {}: {}", path.display(), e))
+}
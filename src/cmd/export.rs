@@ -0,0 +1,40 @@
+// `elephant-diary export`: a diary as Markdown or HTML, to a file or stdout.
+use std::fs;
+use std::io::{self, Write};
+
+use crate::cli::ExportArgs;
+use crate::commit::Diary;
+use crate::export::{self, Format};
+
+pub fn run(args: ExportArgs) -> Result<(), String> {
+    let path = match args.diary {
+        Some(path) => path,
+        None => super::last_opened(args.config)?,
+    };
+    let mut diary = Diary::read_from_path(&path.to_string_lossy())
+        .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    // the page is titled after the diary's file.
+    if diary.name.is_empty() {
+        diary.name = path.to_string_lossy().into_owned();
+    }
+    // --format wins; otherwise the output file's extension says, and stdout gets markdown.
+    let format = match (args.format, &args.output) {
+        (Some(format), _) => format,
+        (None, Some(output)) => Format::from_path(output).ok_or_else(|| {
+            format!(
+                "can't tell the format from {}; say --format md, html, json or jsonl",
+                output.display()
+            )
+        })?,
+        (None, None) => Format::Markdown,
+    };
+    let text = export::export(&diary, format, args.history);
+    match args.output {
+        Some(output) => fs::write(&output, text)
+            .map_err(|e| format!("couldn't write {}: {}", output.display(), e)),
+        None => match io::stdout().lock().write_all(text.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.to_string()),
+            _ => Ok(()),
+        },
+    }
+}
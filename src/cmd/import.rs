@@ -0,0 +1,122 @@
+// `elephant-diary import`: messages from another file, added to a diary.
+use std::fs;
+use std::path::Path;
+
+use crate::cli::ImportArgs;
+use crate::commit::{Diary, Message};
+use crate::import::{self, Imported, Options, Source};
+use crate::store;
+use crate::text::grapheme;
+use crate::util::local_time_string;
+
+// how much of the report lists one thing after another before it says how many more there are.
+const LIST_LIMIT: usize = 10;
+
+pub fn run(args: ImportArgs) -> Result<(), String> {
+    let file = args.file;
+    let text = fs::read_to_string(&file)
+        .map_err(|e| format!("couldn't read {}: {}", file.display(), e))?;
+    let source = match args.source {
+        Some(source) => source,
+        None => Source::guess(&file, &text)
+            .ok_or_else(|| format!("can't tell what {} is; say --format", file.display()))?,
+    };
+    let options = Options {
+        authors: args.authors,
+    };
+    let Imported { diary, skipped } = import::read(&text, source, &options)
+        .map_err(|e| format!("couldn't import {}: {}", file.display(), e))?;
+    let path = match args.diary {
+        Some(path) => path,
+        None => super::last_opened(args.config)?,
+    };
+
+    let total = diary.messages.len();
+    if args.dry_run {
+        let new = new_messages(&path, &diary)?;
+        println!(
+            "would import {} into {}{}",
+            messages(new.len()),
+            path.display(),
+            span(&new)
+        );
+        list(new.iter().map(|msg| {
+            let time = msg.created().map(local_time_string).unwrap_or_default();
+            let text = msg.most_recent().map_or("", |c| c.data());
+            let first = text.lines().next().unwrap_or("");
+            format!("{}  {}", time, grapheme::truncate(first, 60))
+        }));
+        report(total - new.len(), &skipped);
+        return Ok(());
+    }
+    let added = store::merge(&path, diary)
+        .map_err(|e| format!("couldn't import into {}: {}", path.display(), e))?
+        .added;
+    println!("imported {} into {}", messages(added), path.display());
+    report(total - added, &skipped);
+    Ok(())
+}
+
+// the messages in `diary` that importing it into the diary at `path` would add.
+fn new_messages<'a>(path: &Path, diary: &'a Diary) -> Result<Vec<&'a Message>, String> {
+    let existing = if path.exists() {
+        Diary::read_from_path(&path.to_string_lossy())
+            .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?
+    } else {
+        Diary::new()
+    };
+    let mut new: Vec<&Message> = Vec::new();
+    for msg in &diary.messages {
+        let seen = existing.messages.iter().chain(new.iter().copied());
+        if !seen.clone().any(|m| m.same_origin(msg)) {
+            new.push(msg);
+        }
+    }
+    Ok(new)
+}
+
+// ", from 2021-03-04 to 2021-05-01", for messages written on more than one day.
+fn span(msgs: &[&Message]) -> String {
+    let days: Vec<String> = msgs
+        .iter()
+        .filter_map(|m| m.created())
+        .map(|t| local_time_string(t)[..10].to_string())
+        .collect();
+    match (days.iter().min(), days.iter().max()) {
+        (Some(first), Some(last)) if first != last => format!(", from {} to {}", first, last),
+        (Some(day), _) => format!(", on {}", day),
+        _ => String::new(),
+    }
+}
+
+// what didn't make it in, and why.
+fn report(already: usize, skipped: &[String]) {
+    if already > 0 {
+        println!("{} already there", messages(already));
+    }
+    if !skipped.is_empty() {
+        println!("left out {}:", things(skipped.len(), "thing"));
+        list(skipped.iter().cloned());
+    }
+}
+
+fn list(lines: impl ExactSizeIterator<Item = String>) {
+    let more = lines.len().saturating_sub(LIST_LIMIT);
+    for line in lines.take(LIST_LIMIT) {
+        println!("  {}", line);
+    }
+    if more > 0 {
+        println!("  ...and {} more", more);
+    }
+}
+
+fn messages(n: usize) -> String {
+    things(n, "message")
+}
+
+fn things(n: usize, thing: &str) -> String {
+    match n {
+        1 => format!("1 {}", thing),
+        n => format!("{} {}s", n, thing),
+    }
+}
@@ -0,0 +1,105 @@
+// `elephant-diary merge`: two diaries of the same time, made into one.
+use std::path::Path;
+
+use crate::cli::MergeArgs;
+use crate::commit::{Diary, Merged, Message};
+use crate::store;
+use crate::text::grapheme;
+use crate::util::local_time_string;
+
+pub fn run(args: MergeArgs) -> Result<(), String> {
+    let path = match args.diary {
+        Some(path) => path,
+        None => super::last_opened(args.config)?,
+    };
+    let other = read(&args.other)?;
+    let merged = match &args.output {
+        // both diaries are left as they are.
+        Some(output) => {
+            if output.exists() {
+                return Err(format!("{} already exists", output.display()));
+            }
+            let mut diary = read(&path)?;
+            let merged = diary.merge(other);
+            if !args.dry_run {
+                diary.name = output.to_string_lossy().into_owned();
+                store::write(&diary, output)
+                    .map_err(|e| format!("couldn't write {}: {}", output.display(), e))?;
+            }
+            merged
+        }
+        None if args.dry_run => read(&path)?.merge(other),
+        None => store::merge(&path, other)
+            .map_err(|e| format!("couldn't merge into {}: {}", path.display(), e))?,
+    };
+
+    let into = args.output.as_ref().unwrap_or(&path);
+    let verb = if args.dry_run {
+        "would merge"
+    } else {
+        "merged"
+    };
+    println!("{} {} into {}", verb, args.other.display(), into.display());
+    report(&merged, &path, &args.other);
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Diary, String> {
+    Diary::read_from_path(&path.to_string_lossy())
+        .map_err(|e| format!("couldn't read {}: {}", path.display(), e))
+}
+
+fn report(merged: &Merged, ours: &Path, theirs: &Path) {
+    let messages = |n: usize| match n {
+        1 => String::from("1 message"),
+        n => format!("{} messages", n),
+    };
+    println!("  {} added", messages(merged.added));
+    println!("  {} the same in both", messages(merged.same));
+    if merged.updated > 0 {
+        println!(
+            "  {} with newer edits from {}",
+            messages(merged.updated),
+            theirs.display()
+        );
+    }
+    if merged.newer_here > 0 {
+        println!(
+            "  {} with newer edits in {}, left as they were",
+            messages(merged.newer_here),
+            ours.display()
+        );
+    }
+    if merged.conflicts.is_empty() {
+        return;
+    }
+    println!(
+        "  {} edited differently in each, kept with every version from both:",
+        messages(merged.conflicts.len())
+    );
+    let ours = ours.display().to_string();
+    let theirs = theirs.display().to_string();
+    let width = usize::max(grapheme::width(&ours), grapheme::width(&theirs)) + 1;
+    for conflict in &merged.conflicts {
+        let written = conflict.combined.created().map(local_time_string);
+        println!("    written {}", written.unwrap_or_default());
+        for (label, msg) in [
+            (&ours, &conflict.ours),
+            (&theirs, &conflict.theirs),
+            (&String::from("now"), &conflict.combined),
+        ] {
+            println!(
+                "      {:<width$} {}",
+                format!("{}:", label),
+                latest(msg),
+                width = width
+            );
+        }
+    }
+}
+
+// the first line of what a message says now.
+fn latest(msg: &Message) -> String {
+    let text = msg.most_recent().map_or("", |c| c.data());
+    grapheme::truncate(text.lines().next().unwrap_or(""), 60)
+}
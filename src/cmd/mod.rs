@@ -0,0 +1,19 @@
+// The commands that run without the app: they read or write a diary and quit.
+use std::path::PathBuf;
+
+use crate::config::DiaryConfig;
+
+pub mod add;
+pub mod export;
+pub mod import;
+pub mod merge;
+pub mod read;
+
+// the diary to use when none was given: the one opened last.
+fn last_opened(config_path: Option<PathBuf>) -> Result<PathBuf, String> {
+    let config = DiaryConfig::load(config_path).map_err(|e| format!("config: {}", e))?;
+    config
+        .last_opened()
+        .map(|entry| PathBuf::from(&entry.path))
+        .ok_or_else(|| String::from("no diary has been opened yet; say which one with --diary"))
+}
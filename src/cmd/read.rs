@@ -0,0 +1,194 @@
+// `elephant-diary cat`, `tail` and `grep`: a diary printed for reading or for scripts.
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use chrono::prelude::{DateTime, Local, Utc};
+use regex::{Regex, RegexBuilder};
+use serde_derive::Serialize;
+
+use crate::cli::{Read, ReadArgs};
+use crate::commit::{Commit, Diary, Message};
+use crate::util::local_time_string;
+
+// how often tail --follow looks at the diary again.
+const FOLLOW_EVERY: Duration = Duration::from_millis(500);
+
+pub fn run(args: ReadArgs) -> Result<(), String> {
+    let path = match args.diary {
+        Some(path) => path,
+        None => super::last_opened(args.config)?,
+    };
+    let diary = read(&path)?;
+    let out = Output { json: args.json };
+    let printed = match args.what {
+        Read::Cat => out.messages(&diary.messages, 0, None),
+        Read::Tail { lines, follow } => {
+            let skip = diary.messages.len().saturating_sub(lines);
+            let printed = out.messages(&diary.messages[skip..], skip, None);
+            if follow && printed.is_ok() {
+                follow_diary(&path, diary, &out)
+            } else {
+                printed
+            }
+        }
+        Read::Grep {
+            pattern,
+            history,
+            ignore_case,
+        } => {
+            let regex = RegexBuilder::new(&pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map_err(|e| format!("bad pattern: {}", e))?;
+            grep(&diary, &regex, history, &out)
+        }
+    };
+    match printed {
+        // whatever was reading, like `head`, has had enough.
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| e.to_string()),
+    }
+}
+
+fn read(path: &Path) -> Result<Diary, String> {
+    Diary::read_from_path(&path.to_string_lossy())
+        .map_err(|e| format!("couldn't read {}: {}", path.display(), e))
+}
+
+// print the messages whose newest version matches, or with `history`, any version.  the older
+// versions that matched are shown under the message.
+fn grep(diary: &Diary, regex: &Regex, history: bool, out: &Output) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    for (i, msg) in diary.messages.iter().enumerate() {
+        let commits = msg.commits();
+        let latest = commits.last().is_some_and(|c| regex.is_match(c.data()));
+        let older: Vec<&Commit> = if history {
+            commits[..commits.len().saturating_sub(1)]
+                .iter()
+                .filter(|c| regex.is_match(c.data()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if latest || !older.is_empty() {
+            out.message(&mut w, i, msg, history.then_some(&older))?;
+        }
+    }
+    Ok(())
+}
+
+// print messages as they're added to the diary, until killed.
+fn follow_diary(path: &Path, mut diary: Diary, out: &Output) -> io::Result<()> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last_modified = modified(path);
+    loop {
+        thread::sleep(FOLLOW_EVERY);
+        let now = modified(path);
+        if now == last_modified {
+            continue;
+        }
+        // a file that can't be read right now is probably halfway through being replaced.
+        let latest = match read(path) {
+            Ok(latest) => latest,
+            Err(_) => continue,
+        };
+        last_modified = now;
+        for (i, msg) in latest.messages.iter().enumerate() {
+            if !diary.messages.iter().any(|m| m.same_origin(msg)) {
+                out.messages(std::slice::from_ref(msg), i, None)?;
+            }
+        }
+        diary = latest;
+    }
+}
+
+struct Output {
+    json: bool,
+}
+
+// A message as --json prints it.  Times are local, with their offset.
+#[derive(Serialize)]
+struct JsonMessage<'a> {
+    // where it is in the diary, counting from 1.
+    number: usize,
+    created: Option<String>,
+    modified: Option<String>,
+    text: &'a str,
+    edits: usize,
+    // with grep --history: the older versions that matched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    history: Option<Vec<JsonCommit<'a>>>,
+}
+
+#[derive(Serialize)]
+struct JsonCommit<'a> {
+    time: String,
+    text: &'a str,
+}
+
+fn rfc3339(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).to_rfc3339()
+}
+
+impl Output {
+    // print messages, the first of which is message number `first` (from 0) in the diary.
+    fn messages(&self, msgs: &[Message], first: usize, older: Option<&[&Commit]>) -> io::Result<()> {
+        let stdout = io::stdout();
+        let mut w = stdout.lock();
+        for (i, msg) in msgs.iter().enumerate() {
+            self.message(&mut w, first + i, msg, older)?;
+        }
+        w.flush()
+    }
+    fn message(
+        &self,
+        w: &mut impl Write,
+        index: usize,
+        msg: &Message,
+        older: Option<&[&Commit]>,
+    ) -> io::Result<()> {
+        let text = msg.most_recent().map_or("", |c| c.data());
+        if self.json {
+            let json = JsonMessage {
+                number: index + 1,
+                created: msg.created().map(rfc3339),
+                modified: msg.modified().map(rfc3339),
+                text,
+                edits: msg.commits().len().saturating_sub(1),
+                history: older.map(|older| {
+                    older
+                        .iter()
+                        .map(|c| JsonCommit {
+                            time: rfc3339(c.time()),
+                            text: c.data(),
+                        })
+                        .collect()
+                }),
+            };
+            let line = serde_json::to_string(&json).map_err(io::Error::from)?;
+            return writeln!(w, "{}", line);
+        }
+        let time = msg.created().map(local_time_string).unwrap_or_default();
+        let edited = if msg.commits().len() > 1 { " (edited)" } else { "" };
+        print_text(w, &format!("{}{}  ", time, edited), text)?;
+        for commit in older.unwrap_or(&[]) {
+            let was = format!("  was, at {}:  ", local_time_string(commit.time()));
+            print_text(w, &was, commit.data())?;
+        }
+        Ok(())
+    }
+}
+
+// the first line after `label`, and the rest lined up under it.
+fn print_text(w: &mut impl Write, label: &str, text: &str) -> io::Result<()> {
+    let indent = " ".repeat(label.chars().count());
+    for (i, line) in text.split('\n').enumerate() {
+        let start = if i == 0 { label } else { &indent };
+        writeln!(w, "{}{}", start, line)?;
+    }
+    Ok(())
+}
@@ -4,7 +4,7 @@ use std::fmt::{self, Display, Formatter};
 use std::fs;
 use std::io::{self, BufRead};
 use std::str::FromStr;
-// use serde::{Deserialize, Serialize};
+use serde_derive::{Deserialize, Serialize};
 
 const TIME_FORMAT_STRING: &str = "%Y %b %d %H:%M:%S %z";
 
@@ -12,7 +12,7 @@ const TIME_FORMAT_STRING: &str = "%Y %b %d %H:%M:%S %z";
 pub struct FileParseError;
 
 // A file is a list of messages.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Diary {
     pub name: String,
     pub messages: Vec<Message>,
@@ -44,6 +44,86 @@ impl Diary {
     pub fn read_from_path(path: &str) -> io::Result<Diary> {
         Self::from_str(&fs::read_to_string(path)?)
     }
+    // add the messages from `other` that this diary doesn't have yet, in order of when they
+    // were written.  returns how many there were.
+    pub fn take_new_from(&mut self, other: Diary) -> usize {
+        let mut added = 0;
+        for msg in other.messages {
+            if self.messages.iter().any(|m| m.same_origin(&msg)) {
+                continue;
+            }
+            self.insert_in_order(msg);
+            added += 1;
+        }
+        added
+    }
+    // bring `other` into this diary: messages this one doesn't have go in where they belong by
+    // when they were written, and edits made to a message in only one of the two are kept.
+    pub fn merge(&mut self, other: Diary) -> Merged {
+        let mut merged = Merged::default();
+        for msg in other.messages {
+            let mine = match self.messages.iter_mut().find(|m| m.same_origin(&msg)) {
+                Some(mine) => mine,
+                None => {
+                    self.insert_in_order(msg);
+                    merged.added += 1;
+                    continue;
+                }
+            };
+            let before = mine.clone();
+            let taken = mine.take_commits_from(&msg);
+            if taken == 0 {
+                if before.commits.iter().all(|c| msg.has_commit(c)) {
+                    merged.same += 1;
+                } else {
+                    // ours already had every edit theirs did, and more.
+                    merged.newer_here += 1;
+                }
+            } else if before.commits.iter().all(|c| msg.has_commit(c)) {
+                // the other copy just had more edits.
+                merged.updated += 1;
+            } else {
+                merged.conflicts.push(Conflict {
+                    ours: before,
+                    theirs: msg,
+                    combined: mine.clone(),
+                });
+            }
+        }
+        merged
+    }
+    // after the last message written no later than `msg`.
+    fn insert_in_order(&mut self, msg: Message) {
+        let at = self
+            .messages
+            .iter()
+            .rposition(|m| m.created() <= msg.created())
+            .map_or(0, |i| i + 1);
+        self.messages.insert(at, msg);
+    }
+}
+
+// What Diary::merge did.
+#[derive(Debug, Default)]
+pub struct Merged {
+    // messages that were only in the other diary.
+    pub added: usize,
+    // messages that were the same in both.
+    pub same: usize,
+    // messages the other diary had newer edits of.
+    pub updated: usize,
+    // messages this diary had newer edits of; they're left as they were.
+    pub newer_here: usize,
+    // messages edited differently in each; both histories were kept.
+    pub conflicts: Vec<Conflict>,
+}
+
+#[derive(Debug)]
+pub struct Conflict {
+    pub ours: Message,
+    pub theirs: Message,
+    // every version from both, in order.
+    pub combined: Message,
 }
 
 impl Display for Diary {
@@ -68,7 +148,7 @@ impl FromStr for Diary {
         let mut messages = Vec::new();
 
         let mut current_msg = Message::new();
-        while let Some(ln) = lines.next() {
+        for (i, ln) in lines.enumerate() {
             // eprintln!("ln: {}", ln);
             match ln {
                 ";" => {
@@ -76,9 +156,20 @@ impl FromStr for Diary {
                     current_msg = Message::new();
                 }
                 "" => (),
+                // a line starting with the separator continues the last commit's data on a new
+                // line; no timestamp can start that way.
+                s if s.starts_with('|') => {
+                    if let Some(commit) = current_msg.most_recent_mut() {
+                        commit.data.push('\n');
+                        commit.data.push_str(&s[1..]);
+                    }
+                }
                 s => {
-                    // parse the line and add it to the message
-                    current_msg.push_commit(parse_commit_string(s));
+                    // parse the line and add it to the message.  the name is line 1.
+                    let commit = parse_commit_string(s).map_err(|e| {
+                        io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 2, e))
+                    })?;
+                    current_msg.push_commit(commit);
                 }
             }
         }
@@ -88,7 +179,7 @@ impl FromStr for Diary {
 }
 
 // A message is a stack of commits, where the most recent commit is at the top.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     commits: Vec<Commit>,
 }
@@ -106,18 +197,10 @@ impl Message {
         self.commits.is_empty()
     }
     pub fn created(&self) -> Option<DateTime<Utc>> {
-        if let Some(first) = self.commits.first() {
-            Some(first.time)
-        } else {
-            None
-        }
+        self.commits.first().map(|first| first.time)
     }
     pub fn modified(&self) -> Option<DateTime<Utc>> {
-        if let Some(last) = self.commits.last() {
-            Some(last.time)
-        } else {
-            None
-        }
+        self.commits.last().map(|last| last.time)
     }
     pub fn oldest(&self) -> Option<&Commit> {
         self.commits.first()
@@ -125,12 +208,44 @@ impl Message {
     pub fn most_recent(&self) -> Option<&Commit> {
         self.commits.last()
     }
+    // every version of the message, oldest first.
+    pub fn commits(&self) -> &[Commit] {
+        &self.commits
+    }
     pub fn most_recent_mut(&mut self) -> Option<&mut Commit> {
         self.commits.last_mut()
     }
     pub fn push_commit(&mut self, commit: Commit) {
         self.commits.push(commit);
     }
+    // whether `commit` is one of this message's versions, to the second.
+    fn has_commit(&self, commit: &Commit) -> bool {
+        self.commits
+            .iter()
+            .any(|c| c.time.timestamp() == commit.time.timestamp() && c.data == commit.data)
+    }
+    // add the versions of `other` this message doesn't have, keeping them in time order.
+    // returns how many there were.
+    fn take_commits_from(&mut self, other: &Message) -> usize {
+        let new: Vec<Commit> = other
+            .commits
+            .iter()
+            .filter(|c| !self.has_commit(c))
+            .cloned()
+            .collect();
+        let taken = new.len();
+        self.commits.extend(new);
+        self.commits.sort_by_key(|c| c.time);
+        taken
+    }
+    // two copies of the same message: they started out with the same text at the same time.
+    // times only go down to the second in the file, so that's as close as they're compared.
+    pub fn same_origin(&self, other: &Message) -> bool {
+        match (self.oldest(), other.oldest()) {
+            (Some(a), Some(b)) => a.time.timestamp() == b.time.timestamp() && a.data == b.data,
+            _ => false,
+        }
+    }
 }
 
 // Message::to_string() simply writes all commits line by line.  Commits cannot have empty trailing
@@ -146,8 +261,9 @@ impl Display for Message {
 }
 
 // A commit is a string message, as well as a time.
+// Multi-line data is written with every line after the first on its own line, prefixed with '|'.
 // You CANNOT edit a commit, so each commit merely has a time it was created.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Commit {
     time: DateTime<Utc>,
     data: String,
@@ -185,22 +301,62 @@ impl Display for Commit {
             "{}|{}",
             self.time.format(TIME_FORMAT_STRING),
             // if let Some(mtime) = self.modified { format!("{}", mtime.format(TIME_FORMAT_STRING)) } else { "".to_string() },
-            self.data
+            self.data.replace('\n', "\n|")
         )
     }
 }
 
-fn parse_commit_string(commit: &str) -> Commit {
-    let mut time_data_split = commit.splitn(2, '|');
-    let time_str = time_data_split
-        .next()
-        .expect("this is the time part of the string");
+// a commit line is a timestamp and the data, split by the first '|'.
+fn parse_commit_string(commit: &str) -> Result<Commit, String> {
+    let (time_str, data) = commit
+        .split_once('|')
+        .ok_or_else(|| format!("no '|' after the timestamp in \"{}\"", commit))?;
     let time = Utc
         .datetime_from_str(time_str, TIME_FORMAT_STRING)
-        .expect(format!("every timestamp should be formatted properly/ offender: {}, format: {}", time_str, TIME_FORMAT_STRING).as_ref());
-    let data = time_data_split
-        .next()
-        .expect("this is the data part of the string")
-        .to_string();
-    Commit::from(time, data)
+        .map_err(|e| format!("bad timestamp \"{}\" ({})", time_str, e))?;
+    Ok(Commit::from(time, data.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(versions: &[(i64, &str)]) -> Message {
+        let mut msg = Message::new();
+        for &(secs, data) in versions {
+            msg.push_commit(Commit::from(Utc.timestamp(secs, 0), data.to_string()));
+        }
+        msg
+    }
+
+    fn diary(messages: Vec<Message>) -> Diary {
+        Diary::from(String::from("test"), messages)
+    }
+
+    #[test]
+    fn merge_counts_each_kind_of_message() {
+        let mut ours = diary(vec![
+            message(&[(10, "same")]),
+            message(&[(20, "edited"), (25, "edited here")]),
+            message(&[(30, "old")]),
+            message(&[(40, "split"), (45, "this way")]),
+        ]);
+        let theirs = diary(vec![
+            message(&[(10, "same")]),
+            message(&[(20, "edited")]),
+            message(&[(30, "old"), (35, "edited there")]),
+            message(&[(40, "split"), (46, "that way")]),
+            message(&[(50, "new")]),
+        ]);
+        let merged = ours.merge(theirs);
+        assert_eq!(merged.added, 1);
+        assert_eq!(merged.same, 1);
+        assert_eq!(merged.newer_here, 1);
+        assert_eq!(merged.updated, 1);
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(ours.messages.len(), 5);
+        assert_eq!(ours.messages[1].commits().len(), 2);
+        assert_eq!(ours.messages[2].commits().len(), 2);
+        assert_eq!(ours.messages[3].commits().len(), 3);
+    }
 }
@@ -1,20 +1,196 @@
-use std::collections::VecDeque;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::prelude::{DateTime, Utc};
+use serde::{Deserialize as _, Deserializer};
 use serde_derive::{Serialize, Deserialize};
-use confy;
 
+use crate::keymap::KeymapProfile;
+use crate::theme::ThemeConfig;
+
+// missing fields fall back to their defaults, so older config files still load.
 #[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DiaryConfig {
-    pub mru: VecDeque<String>,
+    // where the config was loaded from, when it isn't the usual place.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    // the file was there but couldn't be read, so these are the defaults standing in for it;
+    // storing them would throw away whatever the user had in it.
+    #[serde(skip)]
+    pub unreadable: bool,
+    // also send copied and cut text to the system clipboard, using OSC 52.
+    pub osc52_clipboard: bool,
+    // show a line of the most useful keys for the current mode above the status bar.
+    pub hint_bar: bool,
+    // how the start screen orders the diaries that aren't pinned: "recent" or "name".
+    pub mru_sort: MruSort,
+    // drop diaries that no longer exist from the list at startup, instead of flagging them.
+    pub prune_missing: bool,
+    // which bindings to use while typing: "default", "vi" or "emacs".
+    pub keymap: KeymapProfile,
+    // key bindings on top of the keymap's, per context, e.g.
+    //   [keys.writing]
+    //   "C-s" = "commit"
+    // the actions are read by the keymap, so a misspelled one is only that binding's problem.
+    pub keys: BTreeMap<String, BTreeMap<String, String>>,
+    // a built-in theme and any styles to change in it.
+    pub theme: ThemeConfig,
+    // the diaries on the start screen, in the order they're shown.  toml wants arrays of tables
+    // after everything else, so this stays at the bottom.
+    #[serde(deserialize_with = "read_mru")]
+    pub mru: Vec<MruEntry>,
+}
+
+// how many unpinned diaries the mru list keeps.
+const MRU_LIMIT: usize = 30;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MruSort {
+    // last opened first.
+    #[default]
+    Recent,
+    Name,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MruEntry {
+    pub path: String,
+    // when it was last opened; entries from before this was kept don't have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opened: Option<DateTime<Utc>>,
+    // pinned diaries stay at the top and are never dropped from the list.
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl MruEntry {
+    // the file name without the extension, which is what the start screen calls it.
+    pub fn title(&self) -> String {
+        crate::app::start::diary_title(&self.path)
+    }
+}
+
+// the mru list used to be plain paths, oldest first.  those still load, newest first.
+fn read_mru<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<MruEntry>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Path(String),
+        Entry(MruEntry),
+    }
+    let stored = Vec::<Stored>::deserialize(deserializer)?;
+    let old = stored.iter().all(|s| matches!(s, Stored::Path(_)));
+    let mut mru: Vec<MruEntry> = stored
+        .into_iter()
+        .map(|s| match s {
+            Stored::Path(path) => MruEntry {
+                path,
+                opened: None,
+                pinned: false,
+            },
+            Stored::Entry(entry) => entry,
+        })
+        .collect();
+    if old {
+        mru.reverse();
+    }
+    Ok(mru)
 }
 
 impl DiaryConfig {
-    pub fn update_mru_with(&mut self, filepath: String) {
-        if self.mru.len() > 30 {
-            self.mru.pop_front();
+    // put a diary at the top of the mru list, as opened just now, and save the config.
+    pub fn update_mru_with(&mut self, filepath: String) -> Result<(), String> {
+        let pinned = match self.mru.iter().position(|e| e.path == filepath) {
+            Some(i) => self.mru.remove(i).pinned,
+            None => false,
+        };
+        self.mru.insert(
+            0,
+            MruEntry {
+                path: filepath,
+                opened: Some(Utc::now()),
+                pinned,
+            },
+        );
+        // the least recently opened unpinned diaries go first.
+        let mut unpinned = self.mru.iter().filter(|e| !e.pinned).count();
+        while unpinned > MRU_LIMIT {
+            let oldest = self
+                .mru
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| !e.pinned)
+                .min_by_key(|(_, e)| e.opened)
+                .map(|(i, _)| i)
+                .expect("there are more unpinned entries than the limit");
+            self.mru.remove(oldest);
+            unpinned -= 1;
+        }
+        self.sort_mru();
+        self.store()
+    }
+    // pinned diaries first, then the rest by the chosen order.  diaries opened before their
+    // times were kept sort as the oldest, in the order they were in.
+    pub fn sort_mru(&mut self) {
+        match self.mru_sort {
+            MruSort::Recent => self
+                .mru
+                .sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.opened.cmp(&a.opened))),
+            MruSort::Name => self.mru.sort_by(|a, b| {
+                b.pinned
+                    .cmp(&a.pinned)
+                    .then(a.title().to_lowercase().cmp(&b.title().to_lowercase()))
+            }),
+        }
+    }
+    // the diary opened most recently.  entries from before opening times were kept are newest
+    // first, so with no times at all it's the first one.
+    pub fn last_opened(&self) -> Option<&MruEntry> {
+        self.mru
+            .iter()
+            .filter(|e| e.opened.is_some())
+            .max_by_key(|e| e.opened)
+            .or_else(|| self.mru.first())
+    }
+    // the diaries in the list that can't be found anymore.  pinned ones are left alone: they
+    // might be on a drive that isn't plugged in.  returns how many were dropped.
+    pub fn prune_mru(&mut self) -> usize {
+        let before = self.mru.len();
+        self.mru.retain(|e| e.pinned || Path::new(&e.path).exists());
+        before - self.mru.len()
+    }
+    // the config at `path`, or the usual one.
+    pub fn load(path: Option<PathBuf>) -> Result<Self, confy::ConfyError> {
+        let mut config: DiaryConfig = match &path {
+            Some(path) => confy::load_path(path)?,
+            None => confy::load(crate::util::APP_NAME, None)?,
+        };
+        config.path = path;
+        Ok(config)
+    }
+    // the defaults, for when the config at `path` can't be read.  they're never stored.
+    pub fn unreadable(path: Option<PathBuf>) -> Self {
+        DiaryConfig {
+            path,
+            unreadable: true,
+            ..DiaryConfig::default()
+        }
+    }
+    // the defaults, for tests: they're never stored, so the real config is left alone.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        DiaryConfig::unreadable(None)
+    }
+    pub fn store(&self) -> Result<(), String> {
+        if self.unreadable {
+            return Ok(());
         }
-        if !self.mru.contains(&filepath) {
-            self.mru.push_back(filepath);
+        match &self.path {
+            Some(path) => confy::store_path(path, self),
+            None => confy::store(crate::util::APP_NAME, None, self),
         }
-        confy::store(crate::util::APP_NAME, None, self).expect("failed to write config");
+        .map_err(|e| format!("couldn't save the config: {}", e))
     }
 }
@@ -0,0 +1,97 @@
+// A diary as a web page that needs nothing else: one file, styles included.  Each edited message
+// can carry its earlier versions in a collapsed box underneath.
+use crate::commit::{Diary, Message};
+
+const STYLE: &str = "
+body { max-width: 42rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.5;
+       font-family: system-ui, sans-serif; color: #222; background: #fdfdfb; }
+h1 { font-weight: 600; }
+h2 { font-size: 1rem; color: #777; border-bottom: 1px solid #ddd; margin-top: 2.5rem; }
+.message { display: grid; grid-template-columns: 5.5rem 1fr; gap: 0 1rem; margin: 0.75rem 0; }
+time { color: #999; font-size: 0.85rem; padding-top: 0.15rem; }
+.text { white-space: pre-wrap; overflow-wrap: anywhere; }
+.edited { color: #999; font-size: 0.85rem; }
+details { grid-column: 2; font-size: 0.9rem; color: #666; }
+details ol { margin: 0.25rem 0; padding-left: 1.25rem; }
+details li { margin: 0.25rem 0; }
+@media (prefers-color-scheme: dark) {
+  body { color: #ddd; background: #1b1b1b; }
+  h2 { border-color: #333; }
+  details { color: #aaa; }
+}
+";
+
+pub fn export(diary: &Diary, history: bool) -> String {
+    let title = escape(&super::title(diary));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, STYLE, title
+    );
+    for (day, msgs) in super::days(diary) {
+        out.push_str(&format!(
+            "<section class=\"day\">\n<h2>{}</h2>\n",
+            escape(&super::day_heading(day))
+        ));
+        for msg in msgs {
+            out.push_str(&message(msg, history));
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn message(msg: &Message, history: bool) -> String {
+    let commits = msg.commits();
+    let text = msg.most_recent().map_or("", |c| c.data());
+    let datetime = msg.created().map(|t| t.to_rfc3339()).unwrap_or_default();
+    let edited = if commits.len() > 1 {
+        " <span class=\"edited\">(edited)</span>"
+    } else {
+        ""
+    };
+    let mut out = format!(
+        "<article class=\"message\">\n<time datetime=\"{}\">{}</time>\n\
+         <div class=\"text\">{}{}</div>\n",
+        datetime,
+        escape(&super::time_of_day(msg)),
+        escape(text),
+        edited
+    );
+    if history && commits.len() > 1 {
+        let older = &commits[..commits.len() - 1];
+        let summary = match older.len() {
+            1 => String::from("1 earlier version"),
+            n => format!("{} earlier versions", n),
+        };
+        out.push_str(&format!("<details>\n<summary>{}</summary>\n<ol>\n", summary));
+        for commit in older {
+            out.push_str(&format!(
+                "<li><time datetime=\"{}\">{}</time><div class=\"text\">{}</div></li>\n",
+                commit.time().to_rfc3339(),
+                escape(&crate::util::local_time_string(commit.time())),
+                escape(commit.data())
+            ));
+        }
+        out.push_str("</ol>\n</details>\n");
+    }
+    out.push_str("</article>\n");
+    out
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,25 @@
+// A diary as a Markdown document: a heading per day, and each message under the time it was
+// written.  Messages are already written in markdown, so they go in as they are.
+use crate::commit::Diary;
+
+pub fn export(diary: &Diary) -> String {
+    let mut out = format!("# {}\n", super::title(diary));
+    for (day, msgs) in super::days(diary) {
+        out.push_str(&format!("\n## {}\n", super::day_heading(day)));
+        for msg in msgs {
+            let text = msg.most_recent().map_or("", |c| c.data());
+            let edited = if msg.commits().len() > 1 { " *(edited)*" } else { "" };
+            let time = format!("**{}**{}", super::time_of_day(msg), edited);
+            // one line of plain text sits next to its time; anything bigger, or anything that
+            // means something at the start of a line, gets a paragraph of its own.
+            let block = text.contains('\n')
+                || text.starts_with(['#', '>', '`', '-', '+', '*', '|']);
+            if block {
+                out.push_str(&format!("\n{}\n\n{}\n", time, text));
+            } else {
+                out.push_str(&format!("\n{} — {}\n", time, text));
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,92 @@
+// Diaries turned into something to read or share outside the app: a Markdown document or a web
+// page, both grouped by the day the messages were written in local time, or JSON for other
+// tools to read.
+use std::path::Path;
+
+use chrono::prelude::{Date, Local};
+
+use crate::commit::{Diary, Message};
+use crate::json;
+
+pub mod html;
+pub mod markdown;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+    Json,
+    Jsonl,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "markdown" | "md" => Some(Format::Markdown),
+            "html" | "htm" => Some(Format::Html),
+            "json" => Some(Format::Json),
+            "jsonl" | "ndjson" => Some(Format::Jsonl),
+            _ => None,
+        }
+    }
+    // the format a file name asks for, by its extension.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        path.extension()
+            .and_then(|ext| Format::from_name(&ext.to_string_lossy()))
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Json => "json",
+            Format::Jsonl => "jsonl",
+        }
+    }
+}
+
+// the diary in `format`.  `history` shows every version of an edited message, where the format
+// can tuck them away; json always has them all.
+pub fn export(diary: &Diary, format: Format, history: bool) -> String {
+    match format {
+        Format::Markdown => markdown::export(diary),
+        Format::Html => html::export(diary, history),
+        Format::Json => json::to_json(diary),
+        Format::Jsonl => json::to_jsonl(diary),
+    }
+}
+
+// what to call the diary at the top of the page.
+fn title(diary: &Diary) -> String {
+    match diary.name.as_str() {
+        "" => String::from("Diary"),
+        name => crate::app::start::diary_title(name),
+    }
+}
+
+// the messages, in runs written on the same (local) day.
+fn days(diary: &Diary) -> Vec<(Option<Date<Local>>, Vec<&Message>)> {
+    let mut days: Vec<(Option<Date<Local>>, Vec<&Message>)> = Vec::new();
+    for msg in &diary.messages {
+        let day = msg.created().map(|t| t.with_timezone(&Local).date());
+        match days.last_mut() {
+            Some((last, msgs)) if *last == day => msgs.push(msg),
+            _ => days.push((day, vec![msg])),
+        }
+    }
+    days
+}
+
+// "Saturday, 18 October 2026"
+fn day_heading(day: Option<Date<Local>>) -> String {
+    day.map_or_else(
+        || String::from("Undated"),
+        |day| day.format("%A, %-d %B %Y").to_string(),
+    )
+}
+
+// "10:04 AM"
+fn time_of_day(msg: &Message) -> String {
+    msg.created()
+        .map(|t| t.with_timezone(&Local).format("%-I:%M %p").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,208 @@
+// The JSON exports of other journaling apps.
+//
+// Day One: the Journal.json in its export zip.
+//
+//     { "metadata": { "version": "1.0" },
+//       "entries": [ { "creationDate": "2021-03-04T12:34:56Z", "text": "..." }, ... ] }
+//
+// Journey: one file per entry (or a list of them), with times in milliseconds.
+//
+//     { "date_journal": 1614861296000, "text": "...", ... }
+use chrono::prelude::{DateTime, TimeZone, Utc};
+use serde_derive::Deserialize;
+
+use super::Imported;
+use crate::commit::{Commit, Diary, Message};
+
+#[derive(Deserialize)]
+struct DayOne {
+    entries: Vec<DayOneEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DayOneEntry {
+    creation_date: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct JourneyEntry {
+    date_journal: i64,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Journey {
+    One(JourneyEntry),
+    Many(Vec<JourneyEntry>),
+}
+
+pub fn looks_like_day_one(json: &serde_json::Value) -> bool {
+    json.get("entries").is_some_and(|e| e.is_array())
+}
+
+pub fn looks_like_journey(json: &serde_json::Value) -> bool {
+    match json {
+        serde_json::Value::Array(entries) => entries
+            .first()
+            .is_some_and(|e| e.get("date_journal").is_some()),
+        json => json.get("date_journal").is_some(),
+    }
+}
+
+pub fn read_day_one(text: &str) -> Result<Imported, String> {
+    let export: DayOne = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let mut entries = Vec::new();
+    for (i, entry) in export.entries.into_iter().enumerate() {
+        let written = DateTime::parse_from_rfc3339(&entry.creation_date)
+            .map_err(|e| format!("entry {}: bad creationDate ({})", i + 1, e))?;
+        entries.push((written.with_timezone(&Utc), unescape(&entry.text)));
+    }
+    Ok(imported(entries))
+}
+
+pub fn read_journey(text: &str) -> Result<Imported, String> {
+    let entries = match serde_json::from_str(text).map_err(|e| e.to_string())? {
+        Journey::One(entry) => vec![entry],
+        Journey::Many(entries) => entries,
+    };
+    let mut read = Vec::new();
+    for (i, entry) in entries.into_iter().enumerate() {
+        let written = Utc
+            .timestamp_millis_opt(entry.date_journal)
+            .single()
+            .ok_or_else(|| format!("entry {}: bad date_journal {}", i + 1, entry.date_journal))?;
+        read.push((written, entry.text));
+    }
+    Ok(imported(read))
+}
+
+// a message for each entry with something in it, oldest first.
+fn imported(entries: Vec<(DateTime<Utc>, String)>) -> Imported {
+    let mut skipped = Vec::new();
+    let mut messages = Vec::new();
+    for (i, (written, text)) in entries.into_iter().enumerate() {
+        let text = text.trim_end();
+        if text.trim().is_empty() {
+            skipped.push(format!("entry {}: nothing written", i + 1));
+            continue;
+        }
+        messages.push(Message::from_commit(Commit::from(
+            written,
+            text.to_string(),
+        )));
+    }
+    messages.sort_by_key(|m| m.created());
+    Imported {
+        diary: Diary::from(String::new(), messages),
+        skipped,
+    }
+}
+
+// day one writes markdown with its punctuation escaped, like "done\!".
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '\\' && next.is_ascii_punctuation() => {
+                out.push(*next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_ONE: &str = r#"{
+        "metadata": { "version": "1.0" },
+        "entries": [
+            { "creationDate": "2021-03-05T08:00:00Z", "text": "second, done\\!" },
+            { "creationDate": "2021-03-04T12:34:56+02:00", "text": "first\n" },
+            { "creationDate": "2021-03-06T09:00:00Z", "text": "  " },
+            { "creationDate": "2021-03-07T09:00:00Z" }
+        ]
+    }"#;
+
+    const JOURNEY: &str = r#"[
+        { "date_journal": 1614861296000, "text": "first", "mood": 0 },
+        { "date_journal": 1614931200500, "text": "second" },
+        { "date_journal": 1615000000000, "text": "" }
+    ]"#;
+
+    fn messages(imported: &Imported) -> Vec<(DateTime<Utc>, &str)> {
+        imported
+            .diary
+            .messages
+            .iter()
+            .map(|m| {
+                let commit = m.most_recent().unwrap();
+                (commit.time(), commit.data())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn day_one_entries_keep_their_creation_dates() {
+        let json = serde_json::from_str(DAY_ONE).unwrap();
+        assert!(looks_like_day_one(&json));
+        assert!(!looks_like_journey(&json));
+        let imported = read_day_one(DAY_ONE).unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![
+                (Utc.ymd(2021, 3, 4).and_hms(10, 34, 56), "first"),
+                (Utc.ymd(2021, 3, 5).and_hms(8, 0, 0), "second, done!"),
+            ]
+        );
+        assert_eq!(
+            imported.skipped,
+            vec!["entry 3: nothing written", "entry 4: nothing written"]
+        );
+    }
+
+    #[test]
+    fn day_one_with_a_bad_date_is_an_error() {
+        let json = r#"{ "entries": [ { "creationDate": "yesterday", "text": "hi" } ] }"#;
+        match read_day_one(json) {
+            Err(e) => assert!(e.starts_with("entry 1: bad creationDate"), "{}", e),
+            Ok(_) => panic!("read a date of \"yesterday\""),
+        }
+    }
+
+    #[test]
+    fn journey_times_are_in_milliseconds() {
+        let json = serde_json::from_str(JOURNEY).unwrap();
+        assert!(looks_like_journey(&json));
+        assert!(!looks_like_day_one(&json));
+        let imported = read_journey(JOURNEY).unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![
+                (Utc.ymd(2021, 3, 4).and_hms(12, 34, 56), "first"),
+                (Utc.ymd(2021, 3, 5).and_hms_milli(8, 0, 0, 500), "second"),
+            ]
+        );
+        assert_eq!(imported.skipped, vec!["entry 3: nothing written"]);
+    }
+
+    #[test]
+    fn a_single_journey_entry() {
+        let json = r#"{ "date_journal": 1614861296000, "text": "just this" }"#;
+        assert!(looks_like_journey(&serde_json::from_str(json).unwrap()));
+        let imported = read_journey(json).unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![(Utc.timestamp(1614861296, 0), "just this")]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+// Discord channel exports, as saved by DiscordChatExporter in its JSON format:
+//
+//     { "guild": {...}, "channel": { "name": "vent", ... },
+//       "messages": [ { "type": "Default", "timestamp": "2020-09-05T15:03:00.123+00:00",
+//                       "timestampEdited": null, "content": "...",
+//                       "author": { "id": "...", "name": "...", "nickname": "..." },
+//                       "attachments": [ { "url": "..." } ] }, ... ] }
+//
+// Every message becomes a diary message written when it was sent.  An edited message gets a
+// second commit at the time of the edit; the export only has the text as it ended up, so both
+// commits say the same thing.  There's no keeping pictures, so a message that was only
+// attachments is left out, along with any that are empty.
+use chrono::prelude::{DateTime, Utc};
+use serde_derive::Deserialize;
+
+use super::Imported;
+use crate::commit::{Commit, Diary, Message};
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    guild: Option<Named>,
+    #[serde(default)]
+    channel: Option<Named>,
+    messages: Vec<DiscordMessage>,
+}
+
+#[derive(Deserialize)]
+struct Named {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscordMessage {
+    #[serde(rename = "type", default)]
+    kind: String,
+    timestamp: String,
+    #[serde(default)]
+    timestamp_edited: Option<String>,
+    #[serde(default)]
+    content: String,
+    author: Author,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Author {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    discriminator: String,
+    #[serde(default)]
+    nickname: Option<String>,
+    #[serde(default)]
+    is_bot: bool,
+}
+
+#[derive(Deserialize)]
+struct Attachment {
+    url: String,
+}
+
+impl Author {
+    // `who` is this author's name, nickname, name#discriminator or id.
+    fn is(&self, who: &str) -> bool {
+        let tag = format!("{}#{}", self.name, self.discriminator);
+        [self.name.as_str(), self.id.as_str(), tag.as_str()]
+            .iter()
+            .chain(self.nickname.as_deref().iter())
+            .any(|name| name.eq_ignore_ascii_case(who))
+    }
+}
+
+// whether `json` looks like a discord export rather than some other json.
+pub fn looks_like(json: &serde_json::Value) -> bool {
+    json.get("channel").is_some() && json.get("messages").is_some_and(|m| m.is_array())
+}
+
+// the messages written by any of `authors`; with no authors, the export has to be one person's,
+// not counting bots.
+pub fn read(text: &str, authors: &[String]) -> Result<Imported, String> {
+    let export: Export = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    // joins, pins, calls and the like aren't anything anyone wrote.  messages are numbered by
+    // where they are in the export, for saying which were left out.
+    let written = export
+        .messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.kind.is_empty() || m.kind == "Default" || m.kind == "Reply");
+    let chosen: Vec<(usize, &DiscordMessage)> = if authors.is_empty() {
+        let written = written.filter(|(_, m)| !m.author.is_bot);
+        let mut names: Vec<&str> = written
+            .clone()
+            .map(|(_, m)| m.author.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        if names.len() > 1 {
+            return Err(format!(
+                "there are messages from {}; say whose to import with --author",
+                names.join(", ")
+            ));
+        }
+        written.collect()
+    } else {
+        let chosen: Vec<(usize, &DiscordMessage)> = written
+            .filter(|(_, m)| authors.iter().any(|who| m.author.is(who)))
+            .collect();
+        if chosen.is_empty() {
+            return Err(format!(
+                "none of it was written by {}",
+                authors.join(" or ")
+            ));
+        }
+        chosen
+    };
+
+    let mut messages = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, msg) in chosen {
+        if msg.content.trim().is_empty() {
+            skipped.push(match msg.attachments.len() {
+                0 => format!("message {}: nothing written", i + 1),
+                _ => format!("message {}: only attachments", i + 1),
+            });
+            continue;
+        }
+        // the pictures themselves can't come along, but where they were can.
+        let mut text = msg.content.clone();
+        for attachment in &msg.attachments {
+            text.push('\n');
+            text.push_str(&attachment.url);
+        }
+        let sent = time(&msg.timestamp)?;
+        let mut message = Message::from_commit(Commit::from(sent, text.clone()));
+        if let Some(edited) = &msg.timestamp_edited {
+            message.push_commit(Commit::from(time(edited)?, text));
+        }
+        messages.push(message);
+    }
+    messages.sort_by_key(|m| m.created());
+
+    let name = match (export.guild, export.channel) {
+        (Some(guild), Some(channel)) => format!("{} - {}", guild.name, channel.name),
+        (_, Some(channel)) => channel.name,
+        _ => String::new(),
+    };
+    Ok(Imported {
+        diary: Diary::from(name, messages),
+        skipped,
+    })
+}
+
+fn time(s: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| format!("bad timestamp \"{}\" ({})", s, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::TimeZone;
+
+    const EXPORT: &str = r#"{
+        "guild": { "name": "friends" },
+        "channel": { "name": "vent" },
+        "messages": [
+            { "type": "Default", "timestamp": "2020-09-05T15:03:00.123+00:00",
+              "content": "first", "author": { "id": "1", "name": "sam", "discriminator": "0001" } },
+            { "type": "Default", "timestamp": "2020-09-05T15:04:00+02:00",
+              "timestampEdited": "2020-09-05T16:00:00+00:00",
+              "content": "fixed it", "author": { "id": "1", "name": "sam", "nickname": "Sammy" } },
+            { "type": "Default", "timestamp": "2020-09-05T15:05:00+00:00",
+              "content": "not mine", "author": { "id": "2", "name": "alex" } },
+            { "type": "Default", "timestamp": "2020-09-05T15:06:00+00:00", "content": "",
+              "author": { "id": "1", "name": "sam" },
+              "attachments": [ { "url": "https://cdn.example/cat.png" } ] },
+            { "type": "Default", "timestamp": "2020-09-05T15:07:00+00:00", "content": "  ",
+              "author": { "id": "1", "name": "sam" } },
+            { "type": "Reply", "timestamp": "2020-09-05T15:08:00+00:00",
+              "content": "look", "author": { "id": "1", "name": "sam" },
+              "attachments": [ { "url": "https://cdn.example/dog.png" } ] },
+            { "type": "ChannelPinnedMessage", "timestamp": "2020-09-05T15:09:00+00:00",
+              "content": "pinned a message", "author": { "id": "1", "name": "sam" } },
+            { "type": "Default", "timestamp": "2020-09-05T15:10:00+00:00",
+              "content": "beep", "author": { "id": "3", "name": "bot", "isBot": true } }
+        ]
+    }"#;
+
+    fn sam() -> Vec<String> {
+        vec![String::from("sam")]
+    }
+
+    // each message's versions, as (time, text).
+    fn versions(imported: &Imported) -> Vec<Vec<(DateTime<Utc>, &str)>> {
+        imported
+            .diary
+            .messages
+            .iter()
+            .map(|m| m.commits().iter().map(|c| (c.time(), c.data())).collect())
+            .collect()
+    }
+
+    #[test]
+    fn takes_only_the_authors_messages_at_their_times() {
+        let imported = read(EXPORT, &sam()).unwrap();
+        assert_eq!(imported.diary.name, "friends - vent");
+        assert_eq!(
+            versions(&imported),
+            vec![
+                // 15:04 in +02:00 is before 15:03 in utc.
+                vec![
+                    (Utc.ymd(2020, 9, 5).and_hms(13, 4, 0), "fixed it"),
+                    (Utc.ymd(2020, 9, 5).and_hms(16, 0, 0), "fixed it"),
+                ],
+                vec![(Utc.ymd(2020, 9, 5).and_hms_milli(15, 3, 0, 123), "first")],
+                vec![(
+                    Utc.ymd(2020, 9, 5).and_hms(15, 8, 0),
+                    "look\nhttps://cdn.example/dog.png"
+                )],
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_and_attachment_only_messages_are_skipped() {
+        let imported = read(EXPORT, &sam()).unwrap();
+        assert_eq!(
+            imported.skipped,
+            vec!["message 4: only attachments", "message 5: nothing written"]
+        );
+    }
+
+    #[test]
+    fn authors_can_be_named_any_way() {
+        for who in ["SAM", "1", "sam#0001", "Sammy"] {
+            let imported = read(EXPORT, &[who.to_string()]).unwrap();
+            assert!(!imported.diary.messages.is_empty(), "{}", who);
+        }
+        let both = read(EXPORT, &[String::from("sam"), String::from("alex")]).unwrap();
+        assert_eq!(both.diary.messages.len(), 4);
+        match read(EXPORT, &[String::from("nobody")]) {
+            Err(e) => assert_eq!(e, "none of it was written by nobody"),
+            Ok(_) => panic!("found messages by nobody"),
+        }
+    }
+
+    #[test]
+    fn without_authors_the_export_has_to_be_one_persons() {
+        match read(EXPORT, &[]) {
+            Err(e) => assert!(e.starts_with("there are messages from alex, sam;"), "{}", e),
+            Ok(_) => panic!("took everyone's messages"),
+        }
+        // bots don't count.
+        let alone = EXPORT.replace("\"alex\"", "\"sam\"");
+        assert_eq!(read(&alone, &[]).unwrap().diary.messages.len(), 4);
+    }
+
+    #[test]
+    fn looks_like_an_export() {
+        assert!(looks_like(&serde_json::from_str(EXPORT).unwrap()));
+        assert!(!looks_like(&serde_json::json!({ "messages": [] })));
+    }
+}
@@ -0,0 +1,341 @@
+// Journals kept by hand in a text or Markdown file: a heading for each day, then what happened
+// that day a line (or a bullet) at a time.
+//
+//     # 2021-03-04
+//     - 9:15 coffee with Sam
+//     - the rest of the morning went on the report
+//       which is finally done
+//
+//     ## Friday, 5 March 2021
+//     went for a run
+//
+// A line that starts with a time, like "9:15", "[21:40]" or "**10:01 PM** —", was written then;
+// any other line is taken to be written just after the one before it (or at midnight, for the
+// first of the day).  An indented line that isn't a bullet carries on the message above it.
+// In a plain text file, a line that's nothing but a date is a heading too; a line that only
+// mentions a date is just a message.
+use chrono::prelude::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono::Duration;
+use regex::Regex;
+
+use super::Imported;
+use crate::commit::{Commit, Diary, Message};
+
+// the ways a day might be written, tried in order.
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%Y.%m.%d",
+    "%d %B %Y",
+    "%d %b %Y",
+    "%B %d, %Y",
+    "%b %d, %Y",
+    "%B %d %Y",
+    "%A, %d %B %Y",
+    "%A %d %B %Y",
+    "%A, %B %d, %Y",
+    "%a, %d %b %Y",
+    "%a %d %b %Y",
+];
+
+struct Patterns {
+    // an iso date anywhere in a heading, like "2021-03-04 (a thursday)".
+    iso_date: Regex,
+    bullet: Regex,
+    time: Regex,
+}
+
+impl Patterns {
+    fn new() -> Self {
+        let build = |re: &str| Regex::new(re).expect("the patterns are fine");
+        Patterns {
+            iso_date: build(r"\b(\d{4})-(\d{1,2})-(\d{1,2})\b"),
+            // "- ", "* ", "+ ", "1. ", "2) ", and a task box after any of them.
+            bullet: build(r"^\s*(?:[-*+]|\d+[.)])\s+(?:\[[ xX]\]\s+)?"),
+            time: build(
+                r"^(?:\*\*|\[)?(\d{1,2}):(\d{2})(?::(\d{2}))?(?:\s*([aApP])\.?[mM]\.?)?(?:\*\*|\])?(?:\s*[-–—:]\s+|\s+|$)",
+            ),
+        }
+    }
+
+    // `s` as a date, if that's all it is.
+    fn date(&self, s: &str) -> Option<NaiveDate> {
+        let s = s
+            .trim()
+            .trim_matches(|c| c == '*' || c == '_')
+            .trim_end_matches(':')
+            .trim();
+        DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+    }
+
+    // the date in a "#" heading, which can have more to it than the date.
+    fn heading_date(&self, s: &str) -> Option<NaiveDate> {
+        self.date(s).or_else(|| {
+            let caps = self.iso_date.captures(s)?;
+            let part = |i: usize| caps[i].parse().ok();
+            NaiveDate::from_ymd_opt(part(1)?, part(2)? as u32, part(3)? as u32)
+        })
+    }
+
+    // the time at the start of `line`, and what comes after it.
+    fn time<'a>(&self, line: &'a str) -> Option<(NaiveTime, &'a str)> {
+        let caps = self.time.captures(line)?;
+        let mut hour: u32 = caps[1].parse().ok()?;
+        let minute = caps[2].parse().ok()?;
+        let second = caps.get(3).map_or(Some(0), |s| s.as_str().parse().ok())?;
+        // 12 hour clocks: 12 AM is midnight, 12 PM is noon.
+        if let Some(half) = caps.get(4).map(|m| m.as_str().to_ascii_lowercase()) {
+            if hour == 0 || hour > 12 {
+                return None;
+            }
+            if half == "p" && hour != 12 {
+                hour += 12;
+            } else if half == "a" && hour == 12 {
+                hour = 0;
+            }
+        }
+        let time = NaiveTime::from_hms_opt(hour, minute, second)?;
+        Some((time, &line[caps.get(0)?.end()..]))
+    }
+}
+
+pub fn read(text: &str) -> Result<Imported, String> {
+    let patterns = Patterns::new();
+    let mut messages: Vec<Message> = Vec::new();
+    let mut skipped = Vec::new();
+    let mut day: Option<NaiveDate> = None;
+    // when the last message of the day was written.
+    let mut last: Option<DateTime<Utc>> = None;
+    // a time on a line of its own goes with the line after it.
+    let mut pending: Option<NaiveTime> = None;
+    // whether the last line was a message that an indented line would carry on.
+    let mut open = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let heading = line.strip_prefix('#').map(|h| h.trim_start_matches('#'));
+        let date = match heading {
+            Some(heading) => patterns.heading_date(heading),
+            None if !line.starts_with(char::is_whitespace) => patterns.date(line),
+            None => None,
+        };
+        if let Some(date) = date {
+            day = Some(date);
+            last = None;
+            pending = None;
+            open = false;
+            continue;
+        }
+        if heading.is_some() {
+            skipped.push(format!("line {}: a heading without a date", number));
+            open = false;
+            continue;
+        }
+        let day = match day {
+            Some(day) => day,
+            None => {
+                skipped.push(format!("line {}: comes before the first date", number));
+                continue;
+            }
+        };
+
+        let bullet = patterns.bullet.find(line);
+        if bullet.is_none() && open && line.starts_with(char::is_whitespace) {
+            if let Some(commit) = messages.last_mut().and_then(|m| m.most_recent_mut()) {
+                let data = format!("{}\n{}", commit.data(), line.trim());
+                *commit = Commit::from(commit.time(), data);
+            }
+            continue;
+        }
+        let text = line[bullet.map_or(0, |b| b.end())..].trim();
+        let (time, text) = match patterns.time(text) {
+            Some((time, rest)) => (Some(time), rest.trim()),
+            None => (pending.take(), text),
+        };
+        if text.is_empty() {
+            pending = time;
+            open = false;
+            continue;
+        }
+        let written = match time.and_then(|time| local(day, time)) {
+            Some(written) => written,
+            None => match last {
+                Some(last) => last + Duration::seconds(1),
+                None => local(day, NaiveTime::from_hms(0, 0, 0))
+                    .ok_or_else(|| format!("line {}: {} has no midnight here", number, day))?,
+            },
+        };
+        last = Some(written);
+        messages.push(Message::from_commit(Commit::from(
+            written,
+            text.to_string(),
+        )));
+        open = true;
+    }
+
+    Ok(Imported {
+        diary: Diary::from(String::new(), messages),
+        skipped,
+    })
+}
+
+// a time on `day`, where the diary's being imported.
+fn local(day: NaiveDate, time: NaiveTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&day.and_time(time))
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, time: &str) -> DateTime<Utc> {
+        let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        local(day, NaiveTime::parse_from_str(time, "%H:%M:%S").unwrap()).unwrap()
+    }
+
+    // each message's time and text.
+    fn messages(imported: &Imported) -> Vec<(DateTime<Utc>, String)> {
+        imported
+            .diary
+            .messages
+            .iter()
+            .map(|m| {
+                let commit = m.most_recent().unwrap();
+                (commit.time(), commit.data().to_string())
+            })
+            .collect()
+    }
+
+    fn entry(date: &str, time: &str, text: &str) -> (DateTime<Utc>, String) {
+        (at(date, time), text.to_string())
+    }
+
+    #[test]
+    fn headings_start_days() {
+        let imported = read(
+            "# 2021-03-04\n09:00 woke up\n## Friday, 5 March 2021\nran\n# 2021-03-06 (a saturday)\nslept\n",
+        )
+        .unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![
+                entry("2021-03-04", "09:00:00", "woke up"),
+                entry("2021-03-05", "00:00:00", "ran"),
+                entry("2021-03-06", "00:00:00", "slept"),
+            ]
+        );
+        assert!(imported.skipped.is_empty());
+    }
+
+    #[test]
+    fn headings_without_dates_are_skipped() {
+        let imported = read("# 2021-03-04\n10:00 one\n## thoughts\ntwo\n").unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![
+                entry("2021-03-04", "10:00:00", "one"),
+                entry("2021-03-04", "10:00:01", "two"),
+            ]
+        );
+        assert_eq!(imported.skipped, vec!["line 3: a heading without a date"]);
+    }
+
+    #[test]
+    fn a_plain_line_of_just_a_date_is_a_heading() {
+        let imported = read("March 4, 2021\nwoke up\n**2021/03/05:**\nran\n").unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![
+                entry("2021-03-04", "00:00:00", "woke up"),
+                entry("2021-03-05", "00:00:00", "ran"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_date_in_a_message_is_not_a_heading() {
+        let imported = read(
+            "# 2021-03-04\n09:00 woke up\nRent is due 2021-04-01, remember\n10:00 coffee\n",
+        )
+        .unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![
+                entry("2021-03-04", "09:00:00", "woke up"),
+                entry("2021-03-04", "09:00:01", "Rent is due 2021-04-01, remember"),
+                entry("2021-03-04", "10:00:00", "coffee"),
+            ]
+        );
+        assert!(imported.skipped.is_empty());
+    }
+
+    #[test]
+    fn bullets_and_task_boxes_are_taken_off() {
+        let imported =
+            read("# 2021-03-04\n- [x] 9:15 coffee\n* [ ] call mum\n+ tea\n1. one\n2) two\n")
+                .unwrap();
+        let texts: Vec<String> = messages(&imported).into_iter().map(|(_, t)| t).collect();
+        assert_eq!(texts, vec!["coffee", "call mum", "tea", "one", "two"]);
+        assert_eq!(messages(&imported)[0].0, at("2021-03-04", "09:15:00"));
+    }
+
+    #[test]
+    fn twelve_and_twenty_four_hour_times() {
+        let imported = read(
+            "# 2021-03-04\n12:05 AM late\n9:15 am early\n[12:30] lunch\n**10:01 PM** — bed\n21:40:15 - tea\n",
+        )
+        .unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![
+                entry("2021-03-04", "00:05:00", "late"),
+                entry("2021-03-04", "09:15:00", "early"),
+                entry("2021-03-04", "12:30:00", "lunch"),
+                entry("2021-03-04", "22:01:00", "bed"),
+                entry("2021-03-04", "21:40:15", "tea"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_time_on_its_own_line_goes_with_the_next() {
+        let imported = read("# 2021-03-04\n- 14:00\n- meeting\nafter\n").unwrap();
+        assert_eq!(
+            messages(&imported),
+            vec![
+                entry("2021-03-04", "14:00:00", "meeting"),
+                entry("2021-03-04", "14:00:01", "after"),
+            ]
+        );
+    }
+
+    #[test]
+    fn indented_lines_carry_on_the_message_above() {
+        let imported =
+            read("# 2021-03-04\n- the report\n  which is done\n  - but this is a bullet\n")
+                .unwrap();
+        let texts: Vec<String> = messages(&imported).into_iter().map(|(_, t)| t).collect();
+        assert_eq!(texts, vec!["the report\nwhich is done", "but this is a bullet"]);
+    }
+
+    #[test]
+    fn lines_before_the_first_date_are_skipped() {
+        let imported = read("My journal\n\nby me\n# 2021-03-04\nhello\n").unwrap();
+        assert_eq!(
+            imported.skipped,
+            vec![
+                "line 1: comes before the first date",
+                "line 3: comes before the first date",
+            ]
+        );
+        assert_eq!(messages(&imported).len(), 1);
+    }
+}
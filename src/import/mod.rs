@@ -0,0 +1,89 @@
+// Diaries brought in from files written by something else.
+use std::path::Path;
+
+use crate::commit::Diary;
+use crate::json;
+
+pub mod apps;
+pub mod discord;
+pub mod journal;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Json,
+    Jsonl,
+    Discord,
+    // a journal kept in a text or markdown file.
+    Text,
+    DayOne,
+    Journey,
+}
+
+impl Source {
+    pub fn from_name(name: &str) -> Option<Source> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(Source::Json),
+            "jsonl" | "ndjson" => Some(Source::Jsonl),
+            "discord" => Some(Source::Discord),
+            "text" | "txt" | "markdown" | "md" => Some(Source::Text),
+            "dayone" | "day-one" => Some(Source::DayOne),
+            "journey" => Some(Source::Journey),
+            _ => None,
+        }
+    }
+    // what a file is, by its extension and, for json, by what's in it.
+    pub fn guess(path: &Path, text: &str) -> Option<Source> {
+        match Source::from_name(&path.extension()?.to_string_lossy())? {
+            Source::Json => {
+                let json = serde_json::from_str(text.trim_start_matches('\u{feff}')).ok()?;
+                Some(if discord::looks_like(&json) {
+                    Source::Discord
+                } else if apps::looks_like_day_one(&json) {
+                    Source::DayOne
+                } else if apps::looks_like_journey(&json) {
+                    Source::Journey
+                } else {
+                    Source::Json
+                })
+            }
+            source => Some(source),
+        }
+    }
+}
+
+// what to take from the file, where the source has a choice.
+#[derive(Default)]
+pub struct Options {
+    // the people whose messages to import; empty for everyone's.
+    pub authors: Vec<String>,
+}
+
+// What came out of a file: the messages in it, and why anything in it was left out.
+pub struct Imported {
+    pub diary: Diary,
+    // "line 3: comes before the first date"
+    pub skipped: Vec<String>,
+}
+
+impl Imported {
+    fn all(diary: Diary) -> Self {
+        Imported {
+            diary,
+            skipped: Vec::new(),
+        }
+    }
+}
+
+// read `text`, a file from `source`, as a diary.
+pub fn read(text: &str, source: Source, options: &Options) -> Result<Imported, String> {
+    // some exporters start their files with a byte order mark.
+    let text = text.trim_start_matches('\u{feff}');
+    match source {
+        Source::Json => json::from_json(text).map(Imported::all),
+        Source::Jsonl => json::from_jsonl(text).map(Imported::all),
+        Source::Discord => discord::read(text, &options.authors),
+        Source::Text => journal::read(text),
+        Source::DayOne => apps::read_day_one(text),
+        Source::Journey => apps::read_journey(text),
+    }
+}
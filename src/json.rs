@@ -0,0 +1,124 @@
+// Diaries as JSON, for other tools.  Unlike the diary file, nothing is lost on the way: every
+// commit of every message is kept, with its full UTC time (down to the nanosecond, where the
+// diary file only keeps seconds), so reading back what was written gives the same diary.
+//
+// A .json file is the whole diary in one object:
+//
+//     {
+//       "name": "/home/me/notes.diary",
+//       "messages": [
+//         { "commits": [ { "time": "2026-10-18T22:01:00Z", "data": "hello" },
+//                        { "time": "2026-10-19T08:00:00.250Z", "data": "hello again" } ] }
+//       ]
+//     }
+//
+// A message's commits are its versions, oldest first; the last is what it says now.  Times are
+// RFC 3339, always written in UTC; any offset is accepted when reading.
+//
+// A .jsonl file has the same pieces, one a line: the first line is {"name": ...}, and each line
+// after it is one message, {"commits": [...]}.  Blank lines are skipped.
+use serde_derive::{Deserialize, Serialize};
+
+use crate::commit::{Diary, Message};
+
+// the first line of a .jsonl file.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    name: String,
+}
+
+pub fn to_json(diary: &Diary) -> String {
+    // a diary is strings and times, which always serialize.
+    let mut json = serde_json::to_string_pretty(diary).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+pub fn to_jsonl(diary: &Diary) -> String {
+    let header = Header {
+        name: diary.name.clone(),
+    };
+    let mut jsonl = serde_json::to_string(&header).unwrap_or_default();
+    jsonl.push('\n');
+    for msg in &diary.messages {
+        jsonl.push_str(&serde_json::to_string(msg).unwrap_or_default());
+        jsonl.push('\n');
+    }
+    jsonl
+}
+
+pub fn from_json(json: &str) -> Result<Diary, String> {
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+pub fn from_jsonl(jsonl: &str) -> Result<Diary, String> {
+    let mut lines = jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let header: Header = match lines.next() {
+        Some((i, line)) => {
+            serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e))?
+        }
+        None => return Err(String::from("there's nothing in it")),
+    };
+    let messages = lines
+        .map(|(i, line)| {
+            serde_json::from_str::<Message>(line).map_err(|e| format!("line {}: {}", i + 1, e))
+        })
+        .collect::<Result<Vec<Message>, String>>()?;
+    Ok(Diary::from(header.name, messages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit::Commit;
+    use chrono::prelude::{TimeZone, Utc};
+    use proptest::prelude::*;
+
+    // anything, but especially what the diary file treats specially: line breaks, '|' and ';'.
+    const DATA: &str = "[a-z \n|;\t\"\\\\é🐘\u{0}]{0,24}";
+
+    fn commit() -> impl Strategy<Value = Commit> {
+        // 1900 to 2100, to the nanosecond.
+        (-2_208_988_800i64..4_102_444_800, 0u32..1_000_000_000, DATA)
+            .prop_map(|(secs, nanos, data)| Commit::from(Utc.timestamp(secs, nanos), data))
+    }
+
+    fn diary() -> impl Strategy<Value = Diary> {
+        let message = prop::collection::vec(commit(), 0..5).prop_map(|commits| {
+            let mut msg = Message::new();
+            for commit in commits {
+                msg.push_commit(commit);
+            }
+            msg
+        });
+        (DATA, prop::collection::vec(message, 0..8))
+            .prop_map(|(name, messages)| Diary::from(name, messages))
+    }
+
+    proptest! {
+        #[test]
+        fn json_round_trips(d in diary()) {
+            prop_assert_eq!(from_json(&to_json(&d)).unwrap(), d);
+        }
+
+        #[test]
+        fn jsonl_round_trips(d in diary()) {
+            prop_assert_eq!(from_jsonl(&to_jsonl(&d)).unwrap(), d);
+        }
+    }
+
+    #[test]
+    fn jsonl_is_a_message_a_line() {
+        let mut d = Diary::from(String::from("notes.diary"), Vec::new());
+        d.push_msg(Message::from_commit(Commit::from(
+            Utc.timestamp(1_614_861_296, 250_000_000),
+            String::from("two\nlines|;"),
+        )));
+        let jsonl = to_jsonl(&d);
+        assert_eq!(jsonl.lines().count(), 2);
+        assert!(jsonl.contains("\"2021-03-04T12:34:56.250Z\""));
+    }
+}
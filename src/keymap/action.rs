@@ -0,0 +1,183 @@
+use serde::de::{value::StrDeserializer, Deserialize as _, IntoDeserializer};
+use serde_derive::{Deserialize, Serialize};
+
+// Everything a key can be bound to.  Which ones make sense depends on the context the key is
+// pressed in; binding one somewhere it doesn't apply just does nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    // moving around the app
+    Quit,
+    Help,
+    NewDiary,
+    Open,
+    Browse,
+    Parent,
+    SelectUp,
+    SelectDown,
+    Unselect,
+    // the mru list
+    TogglePin,
+    Remove,
+    Prune,
+    ToggleSort,
+    Insert,
+    Edit,
+    Save,
+    SaveAs,
+    Export,
+    // scrolling the message view, without touching the selection
+    PageUp,
+    PageDown,
+    Oldest,
+    Latest,
+    // answering prompts
+    Confirm,
+    Cancel,
+    NextField,
+    // the help screen
+    Search,
+    // the input bar
+    Commit,
+    Leave,
+    Undo,
+    Redo,
+    ToggleMark,
+    Copy,
+    Cut,
+    Paste,
+    Left,
+    Right,
+    SelectLeft,
+    SelectRight,
+    Backspace,
+    Delete,
+    LineStart,
+    LineEnd,
+    WordForward,
+    WordBack,
+    KillLine,
+    KillLineBack,
+    KillWordBack,
+    Transpose,
+}
+
+impl Action {
+    // the action a config file names, like "select_up".
+    pub fn from_name(name: &str) -> Option<Action> {
+        let name: StrDeserializer<serde::de::value::Error> = name.into_deserializer();
+        Action::deserialize(name).ok()
+    }
+    // what the action does, for the help screen.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::NewDiary => "new diary",
+            Action::Open => "open selected",
+            Action::Browse => "browse for a diary",
+            Action::Parent => "up a folder",
+            Action::SelectUp => "up",
+            Action::SelectDown => "down",
+            Action::Unselect => "unselect",
+            Action::TogglePin => "pin or unpin",
+            Action::Remove => "remove from the list",
+            Action::Prune => "forget missing diaries",
+            Action::ToggleSort => "sort by name or recent",
+            Action::Insert => "write a message",
+            Action::Edit => "edit selected",
+            Action::Save => "save",
+            Action::SaveAs => "save as",
+            Action::Export => "export",
+            Action::PageUp => "scroll up a page",
+            Action::PageDown => "scroll down a page",
+            Action::Oldest => "scroll to the oldest",
+            Action::Latest => "jump to the latest",
+            Action::Confirm => "yes",
+            Action::Cancel => "no",
+            Action::NextField => "next field",
+            Action::Search => "search commands",
+            Action::Commit => "send",
+            Action::Leave => "leave",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::ToggleMark => "mark",
+            Action::Copy => "copy",
+            Action::Cut => "cut",
+            Action::Paste => "paste",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::SelectLeft => "select left",
+            Action::SelectRight => "select right",
+            Action::Backspace => "delete back",
+            Action::Delete => "delete",
+            Action::LineStart => "start of line",
+            Action::LineEnd => "end of line",
+            Action::WordForward => "next word",
+            Action::WordBack => "previous word",
+            Action::KillLine => "kill to end of line",
+            Action::KillLineBack => "kill to start of line",
+            Action::KillWordBack => "kill previous word",
+            Action::Transpose => "swap characters",
+        }
+    }
+}
+
+// Where a key is pressed: the screen, and on the edit screen, the mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Context {
+    Start,
+    Browse,
+    NewDiary,
+    Normal,
+    Writing,
+    Editing,
+    Saving,
+    Help,
+    PreQuit,
+}
+
+impl Context {
+    pub const ALL: [Context; 9] = [
+        Context::Start,
+        Context::Browse,
+        Context::NewDiary,
+        Context::Normal,
+        Context::Writing,
+        Context::Editing,
+        Context::Saving,
+        Context::Help,
+        Context::PreQuit,
+    ];
+    // the name of the context's table in the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Context::Start => "start",
+            Context::Browse => "browse",
+            Context::NewDiary => "new_diary",
+            Context::Normal => "normal",
+            Context::Writing => "writing",
+            Context::Editing => "editing",
+            Context::Saving => "saving",
+            Context::Help => "help",
+            Context::PreQuit => "prequit",
+        }
+    }
+    // the heading for the context's commands on the help screen.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Context::Start => "START",
+            Context::Browse => "OPEN A DIARY",
+            Context::NewDiary => "NEW DIARY",
+            Context::Normal => "NORMAL",
+            Context::Writing => "WRITE",
+            Context::Editing => "EDIT",
+            Context::Saving => "SAVE AS, EXPORT",
+            Context::Help => "HELP",
+            Context::PreQuit => "QUIT?",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Context> {
+        Context::ALL.iter().copied().find(|c| c.name() == name)
+    }
+}
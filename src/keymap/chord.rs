@@ -0,0 +1,120 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+// A key with its modifiers, as written in the config file: "q", "Enter", "C-z", "A-f", "S-Left".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }.normalized()
+    }
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+    // terminals don't agree on how to report some keys, so both sides of a lookup are brought
+    // to the same form first.
+    fn normalized(mut self) -> Self {
+        match self.code {
+            // C-Space arrives as a NUL on some terminals.
+            KeyCode::Null => {
+                self.code = KeyCode::Char(' ');
+                self.modifiers |= KeyModifiers::CONTROL;
+            }
+            // the case of a character already says whether shift was held.
+            KeyCode::Char(c) if self.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.code = KeyCode::Char(c.to_ascii_uppercase());
+                self.modifiers.remove(KeyModifiers::SHIFT);
+            }
+            KeyCode::BackTab => self.modifiers.remove(KeyModifiers::SHIFT),
+            _ => (),
+        }
+        self
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 15] = [
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // modifiers come first, each as a letter and a dash.  a lone "-" is the dash key.
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            match rest.as_bytes()[0] {
+                b'C' => modifiers |= KeyModifiers::CONTROL,
+                b'A' | b'M' => modifiers |= KeyModifiers::ALT,
+                b'S' => modifiers |= KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier in \"{}\"", s)),
+            }
+            rest = &rest[2..];
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ if rest.eq_ignore_ascii_case("Space") => KeyCode::Char(' '),
+            _ => {
+                if let Some((_, code)) = NAMED_KEYS
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(rest))
+                {
+                    *code
+                } else if let Some(n) = rest
+                    .strip_prefix(['F', 'f'])
+                    .and_then(|n| n.parse::<u8>().ok())
+                {
+                    KeyCode::F(n)
+                } else {
+                    return Err(format!("unknown key \"{}\"", s));
+                }
+            }
+        };
+        Ok(KeyChord::new(code, modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "C-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "A-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "S-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => match NAMED_KEYS.iter().find(|(_, named)| *named == code) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", code),
+            },
+        }
+    }
+}
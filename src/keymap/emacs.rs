@@ -0,0 +1,131 @@
+// Readline-style editing operations for the input bar, for those who don't live in vi.
+//
+// The emacs keymap profile binds these to the usual C- and A- chords; they're actions like any
+// other, so they can be bound under any profile.  Kills are handed back to go to the kill ring.
+use super::lines;
+use crate::text::editarea::EditArea;
+use crate::text::word;
+
+pub fn line_start(input: &mut EditArea) {
+    let text = lines::chars(input);
+    input.seek(lines::start(&text, input.cursor()));
+}
+
+pub fn line_end(input: &mut EditArea) {
+    let text = lines::chars(input);
+    input.seek(lines::end(&text, input.cursor()));
+}
+
+pub fn forward_word(input: &mut EditArea) {
+    let text = input.to_string();
+    let end = word::next_word_end(&text, input.cursor()).unwrap_or_else(|| input.len());
+    input.seek(end);
+}
+
+pub fn backward_word(input: &mut EditArea) {
+    let text = input.to_string();
+    input.seek(word::prev_word(&text, input.cursor()).unwrap_or(0));
+}
+
+// kill to the end of the line, or the line break itself at the end of a line.
+pub fn kill_line(input: &mut EditArea) -> String {
+    let text = lines::chars(input);
+    let pos = input.cursor();
+    let le = lines::end(&text, pos);
+    let end = if pos == le {
+        usize::min(le + 1, text.len())
+    } else {
+        le
+    };
+    input.remove_range(pos, end)
+}
+
+pub fn kill_line_back(input: &mut EditArea) -> String {
+    let text = lines::chars(input);
+    let pos = input.cursor();
+    input.remove_range(lines::start(&text, pos), pos)
+}
+
+// kill back to the previous whitespace, like readline's unix-word-rubout.
+pub fn kill_word_back(input: &mut EditArea) -> String {
+    let text = lines::chars(input);
+    let pos = input.cursor();
+    let mut start = pos;
+    while start > 0 && text[start - 1].is_whitespace() {
+        start -= 1;
+    }
+    while start > 0 && !text[start - 1].is_whitespace() {
+        start -= 1;
+    }
+    input.remove_range(start, pos)
+}
+
+// swap the characters around the cursor, or the last two at the end of a line.  a character
+// here is a whole grapheme, so an accented letter or an emoji moves in one piece.
+pub fn transpose(input: &mut EditArea) {
+    let text = lines::chars(input);
+    let pos = input.cursor();
+    let at = if pos == lines::end(&text, pos) {
+        input.prev_boundary(pos)
+    } else {
+        pos
+    };
+    if at > lines::start(&text, pos) && at < text.len() {
+        let start = input.prev_boundary(at);
+        let end = input.next_boundary(at);
+        let swapped = input.slice(at, end) + &input.slice(start, at);
+        // one undo puts them back.
+        input.as_one_step(|input| {
+            input.remove_range(start, end);
+            input.insert_str(&swapped);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str, cursor: usize) -> EditArea {
+        let mut input = EditArea::from_string(text.to_string());
+        input.seek(cursor);
+        input
+    }
+
+    #[test]
+    fn transpose_swaps_around_the_cursor() {
+        let mut input = at("abcd", 2);
+        transpose(&mut input);
+        assert_eq!(input.to_string(), "acbd");
+        assert_eq!(input.cursor(), 3);
+    }
+
+    #[test]
+    fn transpose_at_the_end_of_a_line_swaps_the_last_two() {
+        let mut input = at("abc\ndef", 3);
+        transpose(&mut input);
+        assert_eq!(input.to_string(), "acb\ndef");
+        // nothing to swap at the start of a line.
+        let mut input = at("abc\ndef", 4);
+        transpose(&mut input);
+        assert_eq!(input.to_string(), "abc\ndef");
+    }
+
+    #[test]
+    fn transpose_moves_whole_graphemes() {
+        let mut input = at("e\u{301}👍🏽", 2);
+        transpose(&mut input);
+        assert_eq!(input.to_string(), "👍🏽e\u{301}");
+    }
+
+    #[test]
+    fn transpose_is_one_undo_step() {
+        let mut input = at("abcd", 2);
+        transpose(&mut input);
+        input.undo();
+        assert_eq!(input.to_string(), "abcd");
+        assert_eq!(input.cursor(), 2);
+        input.redo();
+        assert_eq!(input.to_string(), "acbd");
+    }
+}
@@ -0,0 +1,23 @@
+// Finding your way around the lines of the input, for the vi and emacs editing commands.
+// Positions in the input count chars, so these work on the text as a slice of them.
+use crate::text::editarea::EditArea;
+
+pub fn chars(input: &EditArea) -> Vec<char> {
+    input.to_string().chars().collect()
+}
+
+// where the line `pos` is on starts.
+pub fn start(text: &[char], pos: usize) -> usize {
+    text[..pos]
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map_or(0, |i| i + 1)
+}
+
+// where the line `pos` is on ends, before its line break.
+pub fn end(text: &[char], pos: usize) -> usize {
+    text[pos..]
+        .iter()
+        .position(|&c| c == '\n')
+        .map_or(text.len(), |i| pos + i)
+}
@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crossterm::event::KeyEvent;
+use serde_derive::{Deserialize, Serialize};
+
+pub mod action;
+pub mod chord;
+pub mod emacs;
+pub mod lines;
+pub mod vi;
+
+use action::{Action, Context};
+use chord::KeyChord;
+
+// Which set of bindings the input bar uses while writing or editing.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapProfile {
+    // plain arrow keys and the ctrl shortcuts, the same everywhere.
+    #[default]
+    Default,
+    // vi modes inside the input bar: Esc leaves insert mode instead of the input bar.
+    Vi,
+    // readline bindings on top of the default ones; C-y yanks instead of redoing.
+    Emacs,
+}
+
+// The bindings every context starts with, in the order the help screen lists them.
+fn default_bindings(context: Context) -> Vec<(&'static str, Action)> {
+    match context {
+        Context::Start => vec![
+            ("Enter", Action::Open),
+            ("n", Action::NewDiary),
+            ("o", Action::Browse),
+            ("Up", Action::SelectUp),
+            ("Down", Action::SelectDown),
+            ("p", Action::TogglePin),
+            ("d", Action::Remove),
+            ("Delete", Action::Remove),
+            ("x", Action::Prune),
+            ("s", Action::ToggleSort),
+            ("Esc", Action::Unselect),
+            ("h", Action::Help),
+            ("q", Action::Quit),
+        ],
+        Context::Browse => vec![
+            ("Enter", Action::Open),
+            ("Right", Action::Open),
+            ("Backspace", Action::Parent),
+            ("Left", Action::Parent),
+            ("Up", Action::SelectUp),
+            ("Down", Action::SelectDown),
+            ("n", Action::NewDiary),
+            ("Esc", Action::Leave),
+            ("q", Action::Leave),
+            ("h", Action::Help),
+        ],
+        Context::NewDiary => vec![
+            ("Enter", Action::Confirm),
+            ("Tab", Action::NextField),
+            ("BackTab", Action::NextField),
+            ("Esc", Action::Cancel),
+            ("Backspace", Action::Backspace),
+        ],
+        Context::Normal => vec![
+            ("i", Action::Insert),
+            ("e", Action::Edit),
+            ("w", Action::Save),
+            ("W", Action::SaveAs),
+            ("x", Action::Export),
+            ("Up", Action::SelectUp),
+            ("Down", Action::SelectDown),
+            ("PageUp", Action::PageUp),
+            ("PageDown", Action::PageDown),
+            ("Home", Action::Oldest),
+            ("End", Action::Latest),
+            ("Esc", Action::Unselect),
+            ("h", Action::Help),
+            ("q", Action::Quit),
+        ],
+        Context::Writing | Context::Editing => vec![
+            ("Enter", Action::Commit),
+            ("Esc", Action::Leave),
+            ("BackTab", Action::Leave),
+            ("C-z", Action::Undo),
+            ("C-y", Action::Redo),
+            ("C-Space", Action::ToggleMark),
+            ("S-Left", Action::SelectLeft),
+            ("S-Right", Action::SelectRight),
+            ("C-x", Action::Cut),
+            ("C-c", Action::Copy),
+            ("C-v", Action::Paste),
+            ("Left", Action::Left),
+            ("Right", Action::Right),
+            ("Backspace", Action::Backspace),
+            ("Delete", Action::Delete),
+            ("PageUp", Action::PageUp),
+            ("PageDown", Action::PageDown),
+            ("C-Home", Action::Oldest),
+            ("C-End", Action::Latest),
+        ],
+        Context::Saving => vec![
+            ("Enter", Action::Confirm),
+            ("Esc", Action::Cancel),
+            ("Backspace", Action::Backspace),
+        ],
+        Context::Help => vec![
+            ("Up", Action::SelectUp),
+            ("Down", Action::SelectDown),
+            ("/", Action::Search),
+            ("Esc", Action::Leave),
+            ("q", Action::Leave),
+        ],
+        Context::PreQuit => vec![
+            ("y", Action::Confirm),
+            ("Enter", Action::Confirm),
+            ("n", Action::Cancel),
+        ],
+    }
+}
+
+// What a profile adds on top of the defaults; these replace any default on the same key.
+fn profile_bindings(profile: KeymapProfile, context: Context) -> Vec<(&'static str, Action)> {
+    match (profile, context) {
+        (KeymapProfile::Emacs, Context::Writing | Context::Editing) => vec![
+            ("C-a", Action::LineStart),
+            ("C-e", Action::LineEnd),
+            ("C-f", Action::Right),
+            ("C-b", Action::Left),
+            ("A-f", Action::WordForward),
+            ("A-b", Action::WordBack),
+            ("C-k", Action::KillLine),
+            ("C-u", Action::KillLineBack),
+            ("C-w", Action::KillWordBack),
+            ("C-y", Action::Paste),
+            ("C-t", Action::Transpose),
+        ],
+        _ => vec![],
+    }
+}
+
+// The live key bindings: for every context, which chord does what.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    tables: HashMap<Context, Vec<(KeyChord, Action)>>,
+    // everything that went wrong reading the user's bindings, to be shown once the app is up.
+    pub warnings: Vec<String>,
+}
+
+impl Keymap {
+    // build the keymap for a profile, then lay the user's bindings from the config over it.
+    // `overrides` maps a context name to a table of chord strings and actions.
+    pub fn load(
+        profile: KeymapProfile,
+        overrides: &BTreeMap<String, BTreeMap<String, String>>,
+    ) -> Self {
+        let mut keymap = Keymap {
+            tables: HashMap::new(),
+            warnings: Vec::new(),
+        };
+        for context in Context::ALL {
+            let mut table = Vec::new();
+            for (chord, action) in default_bindings(context)
+                .into_iter()
+                .chain(profile_bindings(profile, context))
+            {
+                let chord = chord.parse().expect("the built-in bindings should all parse");
+                bind(&mut table, chord, action);
+            }
+            keymap.tables.insert(context, table);
+        }
+
+        for (name, bindings) in overrides {
+            let context = match Context::from_name(name) {
+                Some(context) => context,
+                None => {
+                    keymap
+                        .warnings
+                        .push(format!("keymap: there is no \"{}\" context", name));
+                    continue;
+                }
+            };
+            // which config entry set each chord, so two spellings of one chord can be caught.
+            let mut seen: HashMap<KeyChord, (&str, Action)> = HashMap::new();
+            for (chord_str, action_name) in bindings {
+                let action = match Action::from_name(action_name) {
+                    Some(action) => action,
+                    None => {
+                        keymap.warnings.push(format!(
+                            "keymap: \"{}\" in [keys.{}] isn't an action",
+                            action_name, name
+                        ));
+                        continue;
+                    }
+                };
+                let chord: KeyChord = match chord_str.parse() {
+                    Ok(chord) => chord,
+                    Err(e) => {
+                        keymap.warnings.push(format!("keymap: {} in [keys.{}]", e, name));
+                        continue;
+                    }
+                };
+                let table = keymap
+                    .tables
+                    .get_mut(&context)
+                    .expect("every context has a table");
+                if let Some((other, other_action)) = seen.get(&chord) {
+                    if *other_action != action {
+                        keymap.warnings.push(format!(
+                            "keymap: \"{}\" and \"{}\" in [keys.{}] are the same key; using {:?}",
+                            other, chord_str, name, action
+                        ));
+                    }
+                } else if let Some(&(_, old)) = table.iter().find(|(bound, _)| *bound == chord) {
+                    if old != action {
+                        keymap.warnings.push(format!(
+                            "keymap: {} in [keys.{}] was {:?}, now {:?}",
+                            chord, name, old, action
+                        ));
+                    }
+                }
+                bind(table, chord, action);
+                seen.insert(chord, (chord_str, action));
+            }
+        }
+        keymap
+    }
+    // what a key does in a context, if anything.
+    pub fn action(&self, context: Context, key: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        self.tables
+            .get(&context)?
+            .iter()
+            .find(|(bound, _)| *bound == chord)
+            .map(|(_, action)| *action)
+    }
+    // every binding in a context, in order.
+    pub fn bindings(&self, context: Context) -> &[(KeyChord, Action)] {
+        self.tables.get(&context).map_or(&[], |table| table.as_ref())
+    }
+}
+
+// bind a chord in a table, replacing whatever it was bound to before.
+fn bind(table: &mut Vec<(KeyChord, Action)>, chord: KeyChord, action: Action) {
+    match table.iter_mut().find(|(bound, _)| *bound == chord) {
+        Some((_, old)) => *old = action,
+        None => table.push((chord, action)),
+    }
+}
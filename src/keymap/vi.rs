@@ -0,0 +1,630 @@
+// A vi layer for the input bar.
+//
+// The input starts out in insert mode, where keys type the way they always do.  Esc drops into
+// normal mode, where keys are motions and operators on the text; Esc again leaves the input bar.
+// Everything here works on an EditArea, so every change is undoable like any other edit.
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::lines;
+use crate::text::editarea::EditArea;
+use crate::text::grapheme;
+use crate::text::killring::KillRing;
+use crate::text::word;
+
+pub enum ViOutcome {
+    // the key did something, or was swallowed by normal mode.
+    Handled,
+    // the key isn't a vi key; let the regular input handling have it.
+    Unhandled,
+    // Esc in normal mode: leave the input bar.
+    Leave,
+}
+
+// normal mode's keys, for the help screen.  these aren't in the keymap, so they can't be rebound.
+pub const NORMAL_KEYS: [(&str, &str); 13] = [
+    ("h j k l", "move"),
+    ("w b e", "word motions"),
+    ("0 ^ $", "line motions"),
+    ("d c y", "delete, change, yank + motion"),
+    ("dd cc yy", "whole lines"),
+    ("x", "delete character"),
+    ("D C", "delete, change to end of line"),
+    ("p P", "paste after, before"),
+    ("i a I A o O", "insert"),
+    ("u C-r", "undo, redo"),
+    (".", "repeat last change"),
+    ("1-9", "count"),
+    ("Esc", "leave"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+impl Operator {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'd' => Some(Operator::Delete),
+            'c' => Some(Operator::Change),
+            'y' => Some(Operator::Yank),
+            _ => None,
+        }
+    }
+}
+
+// where a motion goes, and how an operator should treat the text it covers.
+struct Motion {
+    target: usize,
+    // the character at the target is part of the range, like with `e` and `$`.
+    inclusive: bool,
+    // the range covers whole lines, like with `j` and `k`.
+    linewise: bool,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct ViState {
+    // false while in insert mode.
+    normal: bool,
+    // the count typed so far, e.g. the 3 in 3w.
+    count: Option<usize>,
+    // an operator waiting for its motion: the operator, the key that started it, and its count.
+    operator: Option<(Operator, char, usize)>,
+    // the keys of the command being typed, kept so `.` can replay it.
+    recording: Vec<KeyEvent>,
+    // the count the command being typed started with.
+    change_count: Option<usize>,
+    // set while typing in insert mode after a change command, so the typing is part of it.
+    in_change: bool,
+    last_change: Vec<KeyEvent>,
+    last_count: Option<usize>,
+    replaying: bool,
+    // whether the last thing yanked or deleted was whole lines.
+    linewise: bool,
+}
+
+impl ViState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn is_normal(&self) -> bool {
+        self.normal
+    }
+    // back to insert mode with nothing pending, for a fresh input.  `.` still remembers the last
+    // change.
+    pub fn reset(&mut self) {
+        self.normal = false;
+        self.count = None;
+        self.operator = None;
+        self.recording.clear();
+        self.in_change = false;
+    }
+    pub fn handle(
+        &mut self,
+        input: &mut EditArea,
+        kill_ring: &mut KillRing,
+        key: KeyEvent,
+    ) -> ViOutcome {
+        if self.normal {
+            self.handle_normal(input, kill_ring, key)
+        } else {
+            self.handle_insert(input, key)
+        }
+    }
+    fn handle_insert(&mut self, input: &mut EditArea, key: KeyEvent) -> ViOutcome {
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return ViOutcome::Unhandled;
+        }
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Esc => {
+                if self.in_change {
+                    self.record(key);
+                    self.finish(true);
+                    self.in_change = false;
+                }
+                self.normal = true;
+                // like vi, the cursor steps back onto the last character typed.
+                let text = lines::chars(input);
+                let pos = input.cursor();
+                if pos > lines::start(&text, pos) {
+                    input.seek(input.prev_boundary(pos));
+                }
+            }
+            KeyCode::Char(c) => {
+                self.record_insert(key);
+                input.put(c);
+            }
+            KeyCode::Backspace => {
+                self.record_insert(key);
+                input.back();
+            }
+            KeyCode::Delete => {
+                self.record_insert(key);
+                input.delete();
+            }
+            KeyCode::Left if !shift => {
+                self.record_insert(key);
+                input.left();
+            }
+            KeyCode::Right if !shift => {
+                self.record_insert(key);
+                input.right();
+            }
+            _ => return ViOutcome::Unhandled,
+        }
+        ViOutcome::Handled
+    }
+    fn handle_normal(
+        &mut self,
+        input: &mut EditArea,
+        kill_ring: &mut KillRing,
+        key: KeyEvent,
+    ) -> ViOutcome {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            if key.code == KeyCode::Char('r') {
+                input.redo();
+                self.clamp(input);
+                return ViOutcome::Handled;
+            }
+            return ViOutcome::Unhandled;
+        }
+        let c = match key.code {
+            KeyCode::Esc => {
+                if self.count.is_some() || self.operator.is_some() {
+                    self.count = None;
+                    self.operator = None;
+                    self.recording.clear();
+                    return ViOutcome::Handled;
+                }
+                return ViOutcome::Leave;
+            }
+            KeyCode::Char(c) => c,
+            KeyCode::Left | KeyCode::Backspace => 'h',
+            KeyCode::Right => 'l',
+            KeyCode::Up => 'k',
+            KeyCode::Down => 'j',
+            _ => return ViOutcome::Unhandled,
+        };
+
+        // counts: 0 is a motion unless it continues a count.
+        if let Some(digit) = c.to_digit(10) {
+            if digit != 0 || self.count.is_some() {
+                // counts aren't recorded for `.`; it replays with the command's whole count.
+                self.count = Some(self.count.unwrap_or(0) * 10 + digit as usize);
+                return ViOutcome::Handled;
+            }
+        }
+        if self.operator.is_none() {
+            // a new command starts here.
+            self.recording.clear();
+            self.change_count = self.count;
+        }
+        self.record(key);
+
+        // `.` needs to know whether a count was given at all.
+        let given = self.count.take();
+        let count = given.unwrap_or(1);
+        let text = lines::chars(input);
+        let pos = input.cursor();
+        let ls = lines::start(&text, pos);
+        let le = lines::end(&text, pos);
+
+        if let Some((op, op_key, op_count)) = self.operator.take() {
+            let total = op_count * count;
+            // d2w and 2dw are both two words, and `.` repeats them as d with a count of 2.
+            self.change_count = Some(total);
+            let range = if c == op_key {
+                // dd, cc, yy: whole lines, starting with this one.
+                let mut end = le;
+                for _ in 1..total {
+                    if end >= text.len() {
+                        break;
+                    }
+                    end = lines::end(&text, end + 1);
+                }
+                Some((ls, end, true))
+            } else {
+                // like vi, cw changes to the end of the word instead of eating the whitespace.
+                let c = if op == Operator::Change
+                    && c == 'w'
+                    && text.get(pos).is_some_and(|ch| !ch.is_whitespace())
+                {
+                    'e'
+                } else {
+                    c
+                };
+                self.motion(c, &text, pos, total).map(|m| {
+                    if m.linewise {
+                        let (a, b) = (usize::min(pos, m.target), usize::max(pos, m.target));
+                        (lines::start(&text, a), lines::end(&text, b), true)
+                    } else {
+                        let (a, b) = (usize::min(pos, m.target), usize::max(pos, m.target));
+                        // a word motion stops at the end of the line.
+                        let b = if c == 'w' { usize::min(b, le) } else { b };
+                        let b = if m.inclusive { b + 1 } else { b };
+                        (a, usize::min(b, text.len()), false)
+                    }
+                })
+            };
+            match range {
+                Some((a, b, linewise)) => self.apply(op, a, b, linewise, input, kill_ring),
+                None => self.recording.clear(),
+            }
+            return ViOutcome::Handled;
+        }
+
+        match c {
+            'd' | 'c' | 'y' => {
+                let op = Operator::from_char(c).expect("d, c and y are all operators");
+                self.operator = Some((op, c, count));
+            }
+            'D' => self.apply(Operator::Delete, pos, le, false, input, kill_ring),
+            'C' => self.apply(Operator::Change, pos, le, false, input, kill_ring),
+            'x' => {
+                let end = usize::min(graphemes_forward(&input.to_string(), pos, count), le);
+                self.apply(Operator::Delete, pos, end, false, input, kill_ring);
+            }
+            'p' | 'P' => {
+                self.paste(input, kill_ring, c == 'P', count);
+                self.finish(true);
+            }
+            'i' => self.insert(),
+            'a' => {
+                input.seek(usize::min(input.next_boundary(pos), le));
+                self.insert();
+            }
+            'I' => {
+                input.seek(first_non_blank(&text, pos));
+                self.insert();
+            }
+            'A' => {
+                input.seek(le);
+                self.insert();
+            }
+            'o' => {
+                input.seek(le);
+                input.insert_str("\n");
+                self.insert();
+            }
+            'O' => {
+                input.seek(ls);
+                input.insert_str("\n");
+                input.seek(ls);
+                self.insert();
+            }
+            'u' => {
+                for _ in 0..count {
+                    input.undo();
+                }
+                self.recording.clear();
+                self.clamp(input);
+            }
+            '.' => {
+                self.recording.clear();
+                self.repeat(given, input, kill_ring);
+            }
+            _ => {
+                if let Some(m) = self.motion(c, &text, pos, count) {
+                    input.seek(m.target);
+                    self.clamp(input);
+                }
+                self.recording.clear();
+            }
+        }
+        ViOutcome::Handled
+    }
+    fn motion(&self, c: char, text: &[char], pos: usize, count: usize) -> Option<Motion> {
+        let line = text.iter().collect::<String>();
+        let ls = lines::start(text, pos);
+        let le = lines::end(text, pos);
+        let (target, inclusive, linewise) = match c {
+            'h' => (usize::max(graphemes_back(&line, pos, count), ls), false, false),
+            'l' | ' ' => (usize::min(graphemes_forward(&line, pos, count), le), false, false),
+            'w' => {
+                let mut p = pos;
+                for _ in 0..count {
+                    p = word::next_word(&line, p).unwrap_or(text.len());
+                }
+                (p, false, false)
+            }
+            'b' => {
+                let mut p = pos;
+                for _ in 0..count {
+                    p = word::prev_word(&line, p).unwrap_or(0);
+                }
+                (p, false, false)
+            }
+            'e' => {
+                let mut p = pos;
+                for _ in 0..count {
+                    p = word::word_end(&line, p).unwrap_or(text.len().saturating_sub(1));
+                }
+                (p, true, false)
+            }
+            '0' => (ls, false, false),
+            '^' => (first_non_blank(text, pos), false, false),
+            '$' => (usize::max(ls, le.saturating_sub(1)), le > ls, false),
+            'j' => {
+                let col = pos - ls;
+                let mut start = ls;
+                for _ in 0..count {
+                    let end = lines::end(text, start);
+                    if end >= text.len() {
+                        break;
+                    }
+                    start = end + 1;
+                }
+                (usize::min(start + col, lines::end(text, start)), false, true)
+            }
+            'k' => {
+                let col = pos - ls;
+                let mut start = ls;
+                for _ in 0..count {
+                    if start == 0 {
+                        break;
+                    }
+                    start = lines::start(text, start - 1);
+                }
+                (usize::min(start + col, lines::end(text, start)), false, true)
+            }
+            _ => return None,
+        };
+        Some(Motion {
+            target,
+            inclusive,
+            linewise,
+        })
+    }
+    // run an operator over a range.  linewise ranges don't include the line break at the end.
+    fn apply(
+        &mut self,
+        op: Operator,
+        start: usize,
+        end: usize,
+        linewise: bool,
+        input: &mut EditArea,
+        kill_ring: &mut KillRing,
+    ) {
+        kill_ring.push(input.slice(start, end));
+        self.linewise = linewise;
+        match op {
+            Operator::Yank => {
+                if !linewise && start < input.cursor() {
+                    input.seek(start);
+                }
+                self.finish(false);
+            }
+            Operator::Delete => {
+                if linewise {
+                    // take a line break with the lines, so no empty line is left behind.
+                    let len = input.len();
+                    let (start, end) = if end < len {
+                        (start, end + 1)
+                    } else if start > 0 {
+                        (start - 1, end)
+                    } else {
+                        (start, end)
+                    };
+                    input.remove_range(start, end);
+                    let text = lines::chars(input);
+                    input.seek(lines::start(&text, usize::min(start, text.len())));
+                } else {
+                    input.remove_range(start, end);
+                }
+                self.finish(true);
+                self.clamp(input);
+            }
+            Operator::Change => {
+                input.remove_range(start, end);
+                input.seek(start);
+                self.insert();
+            }
+        }
+    }
+    fn paste(&mut self, input: &mut EditArea, kill_ring: &KillRing, before: bool, count: usize) {
+        let yanked = match kill_ring.yank() {
+            Some(text) => text.to_string(),
+            None => return,
+        };
+        let text = lines::chars(input);
+        let pos = input.cursor();
+        let ls = lines::start(&text, pos);
+        let le = lines::end(&text, pos);
+        if self.linewise {
+            let lines = vec![yanked; count].join("\n");
+            if before {
+                input.seek(ls);
+                input.insert_str(&format!("{}\n", lines));
+                input.seek(ls);
+            } else {
+                input.seek(le);
+                input.insert_str(&format!("\n{}", lines));
+                input.seek(le + 1);
+            }
+        } else {
+            input.seek(if before {
+                pos
+            } else {
+                usize::min(input.next_boundary(pos), le)
+            });
+            input.insert_str(&yanked.repeat(count));
+            // the cursor ends up on the last character pasted.
+            input.seek(input.prev_boundary(input.cursor()));
+        }
+    }
+    // replay the last change, with a new count if one was given.  like vi, a new count sticks
+    // for the next `.` too.
+    fn repeat(&mut self, given: Option<usize>, input: &mut EditArea, kill_ring: &mut KillRing) {
+        if given.is_some() {
+            self.last_count = given;
+        }
+        let count = self.last_count;
+        let keys = self.last_change.clone();
+        self.replaying = true;
+        self.count = count;
+        for key in keys {
+            self.handle(input, kill_ring, key);
+        }
+        self.replaying = false;
+    }
+    fn insert(&mut self) {
+        self.normal = false;
+        self.in_change = true;
+    }
+    fn record(&mut self, key: KeyEvent) {
+        if !self.replaying {
+            self.recording.push(key);
+        }
+    }
+    fn record_insert(&mut self, key: KeyEvent) {
+        if self.in_change {
+            self.record(key);
+        }
+    }
+    // a command is done; remember it for `.` if it changed anything.
+    fn finish(&mut self, change: bool) {
+        if change && !self.replaying {
+            self.last_change = std::mem::take(&mut self.recording);
+            self.last_count = self.change_count;
+        }
+        self.recording.clear();
+    }
+    // in normal mode the cursor sits on a character, so it can't be past the end of a line.
+    fn clamp(&self, input: &mut EditArea) {
+        let text = lines::chars(input);
+        let pos = input.cursor();
+        if pos == lines::end(&text, pos) && pos > lines::start(&text, pos) {
+            input.seek(input.prev_boundary(pos));
+        }
+    }
+}
+
+// `count` graphemes back or forward from `pos`; h and l move over what looks like one character.
+fn graphemes_back(text: &str, pos: usize, count: usize) -> usize {
+    (0..count).fold(pos, |p, _| grapheme::prev(text, p))
+}
+
+fn graphemes_forward(text: &str, pos: usize, count: usize) -> usize {
+    (0..count).fold(pos, |p, _| grapheme::next(text, p))
+}
+
+fn first_non_blank(text: &[char], pos: usize) -> usize {
+    let le = lines::end(text, pos);
+    let mut p = lines::start(text, pos);
+    while p < le && (text[p] == ' ' || text[p] == '\t') {
+        p += 1;
+    }
+    p
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an input holding `text` with the cursor at `cursor`, in normal mode.
+    fn normal(text: &str, cursor: usize) -> (ViState, EditArea, KillRing) {
+        let mut input = EditArea::from_string(text.to_string());
+        input.seek(cursor);
+        let vi = ViState {
+            normal: true,
+            ..ViState::new()
+        };
+        (vi, input, KillRing::new())
+    }
+
+    // type `keys`; "\x1b" is Esc.
+    fn keys(vi: &mut ViState, input: &mut EditArea, kill_ring: &mut KillRing, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\x1b' => KeyCode::Esc,
+                c => KeyCode::Char(c),
+            };
+            vi.handle(input, kill_ring, KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    // the text in the input's buffer, and where the cursor is.
+    fn after(text: &str, cursor: usize, typed: &str) -> (String, usize) {
+        let (mut vi, mut input, mut kill_ring) = normal(text, cursor);
+        keys(&mut vi, &mut input, &mut kill_ring, typed);
+        (input.buffer().to_string(), input.cursor())
+    }
+
+    #[test]
+    fn counts_on_motions() {
+        assert_eq!(after("abcdefgh", 0, "3l").1, 3);
+        assert_eq!(after("abcdefgh", 6, "2h").1, 4);
+        assert_eq!(after("one two three four", 0, "2w").1, 8);
+        assert_eq!(after("one two three four", 18, "3b").1, 4);
+        assert_eq!(after("one two three four", 0, "2e").1, 6);
+        // a count that runs off the end of the line stops there.
+        assert_eq!(after("abc", 0, "10l").1, 2);
+        assert_eq!(after("a\nb\nc\nd", 0, "2j").1, 4);
+    }
+
+    #[test]
+    fn zero_is_a_motion_unless_it_continues_a_count() {
+        assert_eq!(after("abcdef", 4, "0").1, 0);
+        assert_eq!(after("abcdefghijklmnop", 0, "10l").1, 10);
+    }
+
+    #[test]
+    fn operators_take_counts_on_either_side() {
+        let text = "one two three four five";
+        assert_eq!(after(text, 0, "dw").0, "two three four five");
+        assert_eq!(after(text, 0, "2dw").0, "three four five");
+        assert_eq!(after(text, 0, "d2w").0, "three four five");
+        assert_eq!(after(text, 0, "2d2w").0, "five");
+        assert_eq!(after(text, 0, "3x").0, " two three four five");
+        assert_eq!(after("a\nb\nc\nd", 0, "2dd").0, "c\nd");
+    }
+
+    #[test]
+    fn dot_repeats_the_last_change() {
+        let text = "one two three four five";
+        assert_eq!(after(text, 0, "dw.").0, "three four five");
+        assert_eq!(after(text, 0, "dw..").0, "four five");
+        assert_eq!(after("abcdef", 0, "x.").0, "cdef");
+        assert_eq!(after("a\nb\nc", 0, "dd.").0, "c");
+    }
+
+    #[test]
+    fn dot_takes_a_new_count() {
+        let text = "one two three four five six";
+        // dw, then the same three times over.
+        assert_eq!(after(text, 0, "dw3.").0, "five six");
+        assert_eq!(after("abcdefgh", 0, "x3.").0, "efgh");
+        // and the new count sticks.
+        assert_eq!(after("abcdefghij", 0, "x3..").0, "hij");
+        // without one, the original count is used.
+        assert_eq!(after(text, 0, "2dw.").0, "five six");
+        assert_eq!(after(text, 0, "d2w.").0, "five six");
+        // a count after the operator is replaced too, not multiplied.
+        assert_eq!(after("a b c d e f g h i j", 0, "d2w3.").0, "f g h i j");
+        assert_eq!(after("a b c d e f g h i j", 0, "2d2w.").0, "i j");
+    }
+
+    #[test]
+    fn dot_repeats_a_change_with_what_was_typed() {
+        let (text, _) = after("one two three", 0, "cwONE\x1bw.");
+        assert_eq!(text, "ONE ONE three");
+    }
+
+    #[test]
+    fn undo_takes_back_a_whole_command() {
+        assert_eq!(after("one two three", 0, "d2wu").0, "one two three");
+        assert_eq!(after("one two three", 0, "d2wuu").0, "one two three");
+        assert_eq!(after("one two three", 0, "dwdwuu").0, "one two three");
+    }
+
+    #[test]
+    fn yank_and_paste() {
+        assert_eq!(after("ab", 0, "ylp").0, "aab");
+        assert_eq!(after("ab", 0, "yl3p").0, "aaaab");
+        assert_eq!(after("one\ntwo", 0, "yyjp").0, "one\ntwo\none");
+        assert_eq!(after("one\ntwo", 0, "ddp").0, "two\none");
+    }
+}
@@ -2,26 +2,42 @@
 #![allow(unused_imports)]
 
 mod app;
+mod cli;
+mod clipboard;
+mod cmd;
 mod commit;
 mod config;
+mod export;
+mod import;
+mod json;
+mod keymap;
+mod store;
 mod text;
+mod theme;
 mod ui;
 mod util;
 
 use app::app::*;
+use app::start::NewDiaryForm;
 
 use crate::ui::edit::*;
 use crate::ui::help::*;
+use crate::ui::list;
+use crate::ui::small;
+use crate::ui::start::*;
 use crate::util::current_time_string;
-use std::io;
+use std::io::{self, Write};
+use std::path::Path;
 use std::thread;
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+        EnableMouseCapture, Event, KeyCode, KeyEvent,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use textwrap;
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Alignment, Constraint, Corner, Direction, Layout, Rect},
@@ -48,8 +64,16 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     // if there are no routes, don't render anything; the app will close anyway once this function
     // is done. remember, ui draws before app logic updates.
     if let Some(route) = app.route() {
+        // nothing on screen lines up with the mouse targets anymore, so stop taking clicks.
+        app.too_small = small::is_too_small(f.size());
+        if app.too_small {
+            small::too_small_screen(f);
+            return;
+        }
         match route {
             AppRoute::Start => start_screen(f, app),
+            AppRoute::Browse => browse_screen(f, app),
+            AppRoute::NewDiary => new_diary_screen(f, app),
             AppRoute::Edit => edit_screen(f, app),
             AppRoute::Help => help_screen(f, app),
             AppRoute::PreQuit => prequit_screen(f, app),
@@ -58,49 +82,6 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     }
 }
 
-fn start_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage(15),
-                Constraint::Percentage(70),
-                Constraint::Percentage(15),
-            ]
-            .as_ref(),
-        )
-        .split(f.size());
-
-    let center_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Percentage(100)].as_ref())
-        .split(chunks[1]);
-
-    // let middle_block = Block::default().title("mru").borders(Borders::ALL);
-
-    let mru_title = Paragraph::new(Span::styled(
-        "MOST RECENTLY USED: ",
-        Style::default().add_modifier(Modifier::ITALIC),
-    ))
-    .block(Block::default().borders(Borders::ALL));
-
-    // * We actually store the mru in the config.
-    let mru_list_items: Vec<ListItem> = app
-        .config
-        .mru
-        .iter()
-        .map(|x| ListItem::new(x.as_ref()))
-        .collect();
-    let mru_list = List::new(mru_list_items)
-        .block(Block::default().borders(Borders::ALL))
-        .style(Style::default())
-        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-
-    f.render_widget(mru_title, center_chunks[0]);
-    f.render_stateful_widget(mru_list, center_chunks[1], &mut app.select_state);
-}
-
-
 fn prequit_screen<B: Backend>(f: &mut Frame<B>, _app: &mut App) {
     // TODO implement quit screen that is merely a popup window asking if you want to save unsaved
     // work
@@ -110,16 +91,51 @@ fn prequit_screen<B: Backend>(f: &mut Frame<B>, _app: &mut App) {
 }
 
 fn main() -> Result<(), io::Error> {
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Tui(args)) => args,
+        Ok(cli::Command::Add(args)) => return headless(cmd::add::run(args)),
+        Ok(cli::Command::Read(args)) => return headless(cmd::read::run(args)),
+        Ok(cli::Command::Export(args)) => return headless(cmd::export::run(args)),
+        Ok(cli::Command::Import(args)) => return headless(cmd::import::run(args)),
+        Ok(cli::Command::Merge(args)) => return headless(cmd::merge::run(args)),
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Ok(cli::Command::Version) => {
+            println!("elephant-diary {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("elephant-diary: {}\n(elephant-diary --help lists what it takes)", e);
+            std::process::exit(2);
+        }
+    };
+    // anything that goes wrong opening the diary is said here, before the screen is taken over.
+    let app = match setup(args) {
+        Ok(Some(app)) => app,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            eprintln!("elephant-diary: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // raw mode: input is sent raw to the terminal and can be processed as keystrokes.
     enable_raw_mode()?;
 
     let mut stdout = io::stdout();
     // using stdout, allow us to enter an alternate screen where we can also use the mouse.
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // bracketed paste hands us a paste as one event, instead of as a pile of keypresses.
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = App::default();
     run_app(&mut terminal, app)?;
 
     // Restore terminal.
@@ -127,9 +143,62 @@ fn main() -> Result<(), io::Error> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
     Ok(())
 }
+
+// the end of a command that doesn't start the app.
+fn headless(result: Result<(), String>) -> Result<(), io::Error> {
+    if let Err(e) = result {
+        eprintln!("elephant-diary: {}", e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+// get the app ready for what the command line asked for.  None if it turns out there's nothing to
+// do, like when a missing diary shouldn't be made after all.
+fn setup(args: cli::TuiArgs) -> Result<Option<App>, String> {
+    let mut app = App::with_config(args.config);
+    app.read_only = args.read_only;
+    if let Some(name) = args.new {
+        let path = Path::new(&name);
+        let location = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().into_owned(),
+            _ => String::from("."),
+        };
+        let mut form = NewDiaryForm::new(location);
+        form.name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        app.create_at(&form.check()?)?;
+    } else if let Some(path) = args.diary {
+        if path.exists() {
+            app.open_path(&path.to_string_lossy())?;
+        } else if args.read_only {
+            return Err(format!("{} doesn't exist", path.display()));
+        } else if ask(&format!("{} doesn't exist.  Make a new diary there? [Y/n] ", path.display()))
+        {
+            app.create_at(&path)?;
+        } else {
+            return Ok(None);
+        }
+    }
+    Ok(Some(app))
+}
+
+// a yes or no question on the terminal; yes unless the answer starts with n.
+fn ask(question: &str) -> bool {
+    print!("{}", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => !answer.trim_start().to_lowercase().starts_with('n'),
+    }
+}
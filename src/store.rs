@@ -0,0 +1,120 @@
+// Writing diaries to disk so that more than one thing can write to the same diary: the app, and
+// `elephant-diary add` from a shell.  Every write holds a lock file next to the diary and swaps
+// a finished copy in, so nobody reads half a file or writes over someone else's.
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::commit::{Diary, Merged};
+
+// how long to wait for someone else to finish writing.
+const LOCK_WAIT: Duration = Duration::from_secs(3);
+// no write takes this long, so a lock this old was left behind by something that crashed.
+const LOCK_STALE: Duration = Duration::from_secs(30);
+
+// write a diary to `path`, replacing whatever was there.
+pub fn write(diary: &Diary, path: &Path) -> io::Result<()> {
+    let _lock = Lock::take(path)?;
+    replace(path, &diary.to_string())
+}
+
+// save a diary to its own file.  anything added to the file since it was opened, e.g. with
+// `elephant-diary add`, is taken into the diary first instead of being written over.  returns
+// how many messages that was.
+pub fn save(diary: &mut Diary) -> io::Result<usize> {
+    let path = PathBuf::from(&diary.name);
+    let _lock = Lock::take(&path)?;
+    let added = if path.exists() {
+        diary.take_new_from(Diary::read_from_path(&diary.name)?)
+    } else {
+        0
+    };
+    replace(&path, &diary.to_string())?;
+    Ok(added)
+}
+
+// merge `diary` into the diary at `path` (see Diary::merge); if there's no diary there, it's made.
+pub fn merge(path: &Path, diary: Diary) -> io::Result<Merged> {
+    let _lock = Lock::take(path)?;
+    let mut existing = if path.exists() {
+        Diary::read_from_path(&path.to_string_lossy())?
+    } else {
+        Diary::from(path.to_string_lossy().into_owned(), Vec::new())
+    };
+    let merged = existing.merge(diary);
+    replace(path, &existing.to_string())?;
+    Ok(merged)
+}
+
+// add a message to the end of the diary at `path`.
+pub fn append(path: &Path, text: String) -> io::Result<()> {
+    let _lock = Lock::take(path)?;
+    let mut diary = Diary::read_from_path(&path.to_string_lossy())?;
+    diary.push_string(text);
+    replace(path, &diary.to_string())
+}
+
+// write to a hidden file beside `path`, then rename it over `path` in one go.
+fn replace(path: &Path, contents: &str) -> io::Result<()> {
+    let temp = beside(path, "tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+// ".notes.diary.lock" for "notes.diary".
+fn beside(path: &Path, extension: &str) -> PathBuf {
+    let name = path.file_name().map_or_else(
+        || String::from("diary"),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    path.with_file_name(format!(".{}.{}", name, extension))
+}
+
+// Whoever manages to make the lock file gets to write; it's removed when this is dropped.
+struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    fn take(diary: &Path) -> io::Result<Lock> {
+        let path = beside(diary, "lock");
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Lock { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed() > LOCK_WAIT {
+                        return Err(io::Error::new(
+                            io::ErrorKind::WouldBlock,
+                            format!("{} is being written by something else", diary.display()),
+                        ));
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > LOCK_STALE)
+}
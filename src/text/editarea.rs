@@ -1,20 +1,20 @@
-use textwrap;
+use std::fmt::{Display, Formatter};
 use tui::layout::Rect;
 
-use crate::text::gapbuffer::GapBuffer;
 use crate::text::editcursor::EditCursor;
+use crate::text::gapbuffer::GapBuffer;
+use crate::text::grapheme;
+use crate::text::history::{Change, History};
 
-
-const NOTES: &'static str = r#"
+const NOTES: &str = r#"
 An EditArea is composed of the following:
 1. The text that is displayed in the area.
 2. A cursor that exists somewhere in the area.
     : provides the ability to perform motions, such as start of line, prev word
 3. The physical dimensions of the area.
 4. A corresponding block for the area.
-eventually:
-highlighting and selection
-history of edits (for undo/redo)
+5. A history of edits (for undo/redo).
+6. A selection, between the cursor and a mark.
 "#;
 
 #[derive(Default, Debug, Clone)]
@@ -22,10 +22,302 @@ pub struct EditArea {
     buffer: GapBuffer,
     cursor: EditCursor,
     area: Rect,
+    history: History,
 }
 
+// Every edit to the buffer goes through here so that it can be recorded in the history.
 impl EditArea {
     pub fn new() -> Self {
         Self::default()
     }
+    pub fn from_string(s: String) -> Self {
+        Self {
+            buffer: GapBuffer::from_string(s),
+            ..Self::default()
+        }
+    }
+    pub fn buffer(&self) -> &GapBuffer {
+        &self.buffer
+    }
+    pub fn cursor(&self) -> usize {
+        self.buffer.cursor()
+    }
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+    // the selected range, start inclusive and end exclusive, if anything is selected.
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let mark = usize::min(self.cursor.mark()?, self.buffer.len());
+        let cursor = self.buffer.cursor();
+        match mark.cmp(&cursor) {
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Less => Some((mark, cursor)),
+            std::cmp::Ordering::Greater => Some((cursor, mark)),
+        }
+    }
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        Some(self.buffer.slice(start, end))
+    }
+    // the mark key: start a selection at the cursor that follows every motion, or drop it.
+    pub fn toggle_mark(&mut self) {
+        if self.cursor.mark().is_some() {
+            self.cursor.clear_mark();
+        } else {
+            self.cursor.set_mark(self.buffer.cursor(), true);
+        }
+    }
+    pub fn clear_selection(&mut self) {
+        self.cursor.clear_mark();
+    }
+    pub fn select_left(&mut self) {
+        self.start_shift_selection();
+        self.history.seal();
+        self.buffer.seek(self.prev_boundary(self.buffer.cursor()));
+    }
+    pub fn select_right(&mut self) {
+        self.start_shift_selection();
+        self.history.seal();
+        self.buffer.seek(self.next_boundary(self.buffer.cursor()));
+    }
+    // where the grapheme before a position starts: one Left away.
+    pub fn prev_boundary(&self, pos: usize) -> usize {
+        grapheme::prev(&self.buffer.to_string(), pos)
+    }
+    // where the grapheme after a position ends: one Right away.
+    pub fn next_boundary(&self, pos: usize) -> usize {
+        grapheme::next(&self.buffer.to_string(), pos)
+    }
+    // remove the selected text as a single undo step, and hand it back.
+    pub fn cut_selection(&mut self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        self.cursor.clear_mark();
+        Some(self.remove_range(start, end))
+    }
+    // the text between two positions, end exclusive.
+    pub fn slice(&self, start: usize, end: usize) -> String {
+        self.buffer.slice(start, end)
+    }
+    // remove the text between two positions as a single undo step, and hand it back.  the cursor
+    // ends up where the text was.
+    pub fn remove_range(&mut self, start: usize, end: usize) -> String {
+        let end = usize::min(end, self.buffer.len());
+        if start >= end {
+            return String::new();
+        }
+        let text: Vec<char> = self.buffer.slice(start, end).chars().collect();
+        let cursor = self.buffer.cursor();
+        self.buffer.seek(start);
+        for _ in start..end {
+            self.buffer.delete();
+        }
+        self.history.record(Change::Remove(start, text.clone()), cursor, false);
+        text.into_iter().collect()
+    }
+    // insert a whole string at the cursor as a single undo step, replacing the selection.
+    pub fn insert_str(&mut self, s: &str) {
+        self.as_one_step(|input| {
+            input.cut_selection();
+            if s.is_empty() {
+                return;
+            }
+            let at = input.buffer.cursor();
+            let text: Vec<char> = s.chars().collect();
+            for &ch in &text {
+                input.buffer.put(ch);
+            }
+            input.history.record(Change::Insert(at, text), at, false);
+        })
+    }
+    // make every edit done in `f` a single undo step.
+    pub fn as_one_step<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.history.begin_group();
+        let result = f(self);
+        self.history.end_group();
+        result
+    }
+    pub fn put(&mut self, ch: char) {
+        // typing over a selection replaces it.
+        self.cut_selection();
+        let at = self.buffer.cursor();
+        self.buffer.put(ch);
+        self.history.record(Change::Insert(at, vec![ch]), at, true);
+    }
+    // backspace and delete take out a whole grapheme, so an emoji goes in one keypress.
+    pub fn back(&mut self) {
+        if self.cut_selection().is_some() {
+            return;
+        }
+        let at = self.buffer.cursor();
+        let start = self.prev_boundary(at);
+        if start < at {
+            let text: Vec<char> = self.buffer.slice(start, at).chars().collect();
+            for _ in start..at {
+                self.buffer.back();
+            }
+            self.history.record(Change::Remove(start, text), at, true);
+        }
+    }
+    pub fn delete(&mut self) {
+        if self.cut_selection().is_some() {
+            return;
+        }
+        let at = self.buffer.cursor();
+        let end = self.next_boundary(at);
+        if at < end {
+            let text: Vec<char> = self.buffer.slice(at, end).chars().collect();
+            for _ in at..end {
+                self.buffer.delete();
+            }
+            self.history.record(Change::Remove(at, text), at, true);
+        }
+    }
+    // moving the cursor ends the current run of typing, so the next keypress starts a new undo
+    // step.  it also drops a shift-selection, but not one started with the mark key.
+    pub fn left(&mut self) {
+        self.end_shift_selection();
+        self.history.seal();
+        self.buffer.seek(self.prev_boundary(self.buffer.cursor()));
+    }
+    pub fn right(&mut self) {
+        self.end_shift_selection();
+        self.history.seal();
+        self.buffer.seek(self.next_boundary(self.buffer.cursor()));
+    }
+    pub fn seek(&mut self, place: usize) {
+        self.end_shift_selection();
+        self.history.seal();
+        self.buffer.seek(usize::min(place, self.buffer.len()));
+    }
+    fn start_shift_selection(&mut self) {
+        if self.cursor.mark().is_none() {
+            self.cursor.set_mark(self.buffer.cursor(), false);
+        }
+    }
+    fn end_shift_selection(&mut self) {
+        if !self.cursor.is_sticky() {
+            self.cursor.clear_mark();
+        }
+    }
+    pub fn undo(&mut self) {
+        self.cursor.clear_mark();
+        if let Some(step) = self.history.undo() {
+            for change in step.changes.iter().rev() {
+                self.apply(&change.inverse());
+            }
+            self.buffer.seek(step.cursor);
+        }
+    }
+    pub fn redo(&mut self) {
+        self.cursor.clear_mark();
+        if let Some(step) = self.history.redo() {
+            for change in &step.changes {
+                self.apply(change);
+            }
+            self.buffer.seek(step.cursor_after());
+        }
+    }
+    // empties the buffer and forgets its history.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.history.clear();
+        self.cursor.clear_mark();
+    }
+    // replay a change against the buffer without recording it.
+    fn apply(&mut self, change: &Change) {
+        match change {
+            Change::Insert(at, text) => {
+                self.buffer.seek(*at);
+                for &ch in text {
+                    self.buffer.put(ch);
+                }
+            }
+            Change::Remove(at, text) => {
+                self.buffer.seek(*at);
+                for _ in text {
+                    self.buffer.delete();
+                }
+            }
+        }
+    }
+}
+
+impl Display for EditArea {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an area holding `text`, with `start..end` selected and the cursor at `end`.
+    fn selecting(text: &str, start: usize, end: usize) -> EditArea {
+        let mut input = EditArea::from_string(text.to_string());
+        input.seek(start);
+        input.toggle_mark();
+        input.seek(end);
+        input
+    }
+
+    #[test]
+    fn pasting_over_a_selection_is_one_undo_step() {
+        let mut input = selecting("hello world", 6, 11);
+        input.insert_str("there");
+        assert_eq!(input.to_string(), "hello there");
+        input.undo();
+        assert_eq!(input.to_string(), "hello world");
+        assert_eq!(input.cursor(), 11);
+        input.redo();
+        assert_eq!(input.to_string(), "hello there");
+        assert_eq!(input.cursor(), 11);
+        input.undo();
+        input.undo();
+        assert_eq!(input.to_string(), "hello world");
+    }
+
+    #[test]
+    fn pasting_nothing_over_a_selection_still_undoes() {
+        let mut input = selecting("abc", 0, 2);
+        input.insert_str("");
+        assert_eq!(input.to_string(), "c");
+        input.undo();
+        assert_eq!(input.to_string(), "abc");
+    }
+
+    #[test]
+    fn typing_runs_merge_but_not_into_a_paste() {
+        let mut input = EditArea::new();
+        input.insert_str("ab");
+        input.put('c');
+        input.put('d');
+        assert_eq!(input.to_string(), "abcd");
+        input.undo();
+        assert_eq!(input.to_string(), "ab");
+        input.undo();
+        assert_eq!(input.to_string(), "");
+    }
+
+    #[test]
+    fn nested_steps_are_one_step() {
+        let mut input = EditArea::from_string("one two".to_string());
+        input.seek(0);
+        input.put('x');
+        input.as_one_step(|input| {
+            input.remove_range(0, 4);
+            input.as_one_step(|input| input.insert_str("2 "));
+            input.seek(input.len());
+            input.put('!');
+        });
+        assert_eq!(input.to_string(), "2  two!");
+        input.undo();
+        assert_eq!(input.to_string(), "xone two");
+        assert_eq!(input.cursor(), 1);
+        input.undo();
+        assert_eq!(input.to_string(), "one two");
+    }
 }
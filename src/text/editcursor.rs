@@ -2,7 +2,26 @@
 #[derive(Default, Debug, Clone)]
 pub struct EditCursor {
     pos: (usize, usize),    // row, col
+    // the other end of the selection, if any; the cursor itself is always the moving end.
+    mark: Option<usize>,
+    // a mark set with the mark key stays put while the cursor moves.  one set by shift-selecting
+    // goes away on the next unshifted motion.
+    sticky: bool,
 }
 
-
-
+impl EditCursor {
+    pub fn mark(&self) -> Option<usize> {
+        self.mark
+    }
+    pub fn is_sticky(&self) -> bool {
+        self.sticky
+    }
+    pub fn set_mark(&mut self, at: usize, sticky: bool) {
+        self.mark = Some(at);
+        self.sticky = sticky;
+    }
+    pub fn clear_mark(&mut self) {
+        self.mark = None;
+        self.sticky = false;
+    }
+}
@@ -6,7 +6,7 @@ use std::fmt::{Display, Formatter};
 // punctuation breakpoint.
 
 const GAP_GROWTH: usize = 256;
-const GAP_FILL: &'static str = "                                                                                                                                                                                                                                                                ";
+const GAP_FILL: &str = "                                                                                                                                                                                                                                                                ";
 
 // #[derive(Default, Debug, Clone)]
 // pub struct Input {
@@ -15,7 +15,7 @@ const GAP_FILL: &'static str = "
 //     // selected text
 // }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct GapBuffer {
     buffer: Vec<char>, // yes, Vec<char> is fine for our purposes.  you do NOT want to use String; it's a hassle.
     gap_start: usize,
@@ -42,6 +42,9 @@ impl GapBuffer {
         gb
     }
     pub fn from_string(s: String) -> Self {
+        if s.is_empty() {
+            return Self::new();
+        }
         let buffer: Vec<char> = s.chars().collect();
         let mut gb = Self {
             gap_start: buffer.len(),
@@ -61,6 +64,37 @@ impl GapBuffer {
     pub fn cursor(&self) -> usize {
         self.gap_start
     }
+    // the number of characters in the text, not counting the gap.
+    pub fn len(&self) -> usize {
+        self.buf_len() - self.gap_len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    // the character at a position in the text, skipping over the gap.
+    pub fn get(&self, idx: usize) -> Option<char> {
+        if idx < self.gap_start {
+            self.buffer.get(idx).copied()
+        } else {
+            self.buffer.get(idx + self.gap_len()).copied()
+        }
+    }
+    // the text between two positions, end exclusive.
+    pub fn slice(&self, start: usize, end: usize) -> String {
+        (start..end).filter_map(|i| self.get(i)).collect()
+    }
+    // the character the cursor is right after, i.e. the one `back` would remove.
+    pub fn char_before(&self) -> Option<char> {
+        if self.gap_start > 0 {
+            Some(self.buffer[self.gap_start - 1])
+        } else {
+            None
+        }
+    }
+    // the character the cursor is right before, i.e. the one `delete` would remove.
+    pub fn char_after(&self) -> Option<char> {
+        self.buffer.get(self.gap_end + 1).copied()
+    }
     pub fn gap_start(&self) -> usize {
         self.gap_start
     }
@@ -208,6 +242,12 @@ impl GapBuffer {
     // abc__d, s=3, e=4 (2 + GAP__GROWTH)
 }
 
+impl Default for GapBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Display for GapBuffer {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         let pregap = self.buffer.iter().take(self.gap_start).collect::<String>();
@@ -0,0 +1,203 @@
+// Positions in the input are counted in chars, but a user-perceived character can be several of
+// them (an emoji with a skin tone, a ZWJ family, a letter with combining accents), and can take
+// up two columns on screen (CJK, most emoji).  These keep the cursor on whole graphemes and
+// translate between chars and screen columns.
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// the char positions where graphemes start, plus the end of the text.
+fn boundaries(text: &str) -> impl Iterator<Item = usize> + '_ {
+    std::iter::once(0).chain(text.graphemes(true).scan(0, |chars, g| {
+        *chars += g.chars().count();
+        Some(*chars)
+    }))
+}
+
+// where the grapheme before `pos` starts.
+pub fn prev(text: &str, pos: usize) -> usize {
+    boundaries(text).take_while(|&b| b < pos).last().unwrap_or(0)
+}
+
+// where the grapheme after `pos` ends; `pos` itself at the end of the text.
+pub fn next(text: &str, pos: usize) -> usize {
+    boundaries(text).find(|&b| b > pos).unwrap_or(pos)
+}
+
+// how many columns a string takes up on screen.
+pub fn width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+// how many columns the first `chars` chars of a line take up.
+pub fn column(line: &str, chars: usize) -> usize {
+    let end = line.char_indices().nth(chars).map_or(line.len(), |(i, _)| i);
+    width(&line[..end])
+}
+
+// the char position in a line that's drawn at screen column `col`.  a click on the right half
+// of a wide character still lands before it.
+pub fn at_column(line: &str, col: usize) -> usize {
+    let mut used = 0;
+    let mut chars = 0;
+    for g in line.graphemes(true) {
+        let w = width(g);
+        if used + w > col {
+            break;
+        }
+        used += w;
+        chars += g.chars().count();
+    }
+    chars
+}
+
+// split a string into pieces no wider than `max` columns, without splitting graphemes.  the
+// first piece only gets `first` columns.
+pub fn split_to_width(s: &str, first: usize, max: usize) -> Vec<String> {
+    let mut pieces = vec![String::new()];
+    let mut room = first;
+    for g in s.graphemes(true) {
+        let w = width(g);
+        let last = pieces.last().expect("there is always a piece");
+        // a grapheme wider than a whole line gets a line to itself.
+        if w > room && !(last.is_empty() && room == max) {
+            pieces.push(String::new());
+            room = max;
+        }
+        room = room.saturating_sub(w);
+        pieces.last_mut().expect("there is always a piece").push_str(g);
+    }
+    pieces
+}
+
+// cut a string down to `max` columns, ending it with "…" if anything was cut.
+pub fn truncate(s: &str, max: usize) -> String {
+    if width(s) <= max {
+        return s.to_string();
+    }
+    if max <= 1 {
+        return "…".repeat(max);
+    }
+    let mut cut = split_to_width(s, max - 1, max - 1).remove(0);
+    cut.push('…');
+    cut
+}
+
+// the end of a string that fits in `max` columns, for a field where the cursor is at the end.
+pub fn tail(s: &str, max: usize) -> &str {
+    let mut used = 0;
+    let mut start = s.len();
+    for (i, g) in s.grapheme_indices(true).rev() {
+        used += width(g);
+        if used > max {
+            break;
+        }
+        start = i;
+    }
+    &s[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a ZWJ family: five chars, one grapheme.
+    const FAMILY: &str = "👨\u{200d}👩\u{200d}👧";
+
+    #[test]
+    fn prev_and_next_step_over_whole_graphemes() {
+        let s = format!("a{}b", FAMILY);
+        assert_eq!(next(&s, 0), 1);
+        assert_eq!(next(&s, 1), 6);
+        assert_eq!(next(&s, 6), 7);
+        assert_eq!(next(&s, 7), 7);
+        assert_eq!(prev(&s, 7), 6);
+        assert_eq!(prev(&s, 6), 1);
+        assert_eq!(prev(&s, 1), 0);
+        assert_eq!(prev(&s, 0), 0);
+        // from the middle of one, out to its edges.
+        assert_eq!(next(&s, 3), 6);
+        assert_eq!(prev(&s, 3), 1);
+    }
+
+    #[test]
+    fn skin_tones_and_combining_marks_are_one_grapheme() {
+        assert_eq!(next("👍🏽x", 0), 2);
+        assert_eq!(prev("👍🏽x", 2), 0);
+        assert_eq!(next("e\u{301}f", 0), 2);
+        assert_eq!(prev("e\u{301}f", 3), 2);
+    }
+
+    #[test]
+    fn rtl_text_moves_a_letter_at_a_time() {
+        let s = "שלום";
+        assert_eq!(next(s, 0), 1);
+        assert_eq!(prev(s, 4), 3);
+        assert_eq!(column(s, 2), 2);
+        assert_eq!(at_column(s, 3), 3);
+    }
+
+    #[test]
+    fn column_counts_wide_characters_twice() {
+        assert_eq!(column("a漢b", 0), 0);
+        assert_eq!(column("a漢b", 2), 3);
+        assert_eq!(column("a漢b", 3), 4);
+        assert_eq!(column("a漢b", 10), 4);
+        assert_eq!(column("🐘🐘", 1), 2);
+    }
+
+    #[test]
+    fn at_column_lands_before_a_wide_character() {
+        assert_eq!(at_column("a漢b", 0), 0);
+        assert_eq!(at_column("a漢b", 1), 1);
+        // the right half of 漢.
+        assert_eq!(at_column("a漢b", 2), 1);
+        assert_eq!(at_column("a漢b", 3), 2);
+        assert_eq!(at_column("a漢b", 99), 3);
+        let s = format!("{}x", FAMILY);
+        assert_eq!(at_column(&s, width(FAMILY) - 1), 0);
+        assert_eq!(at_column(&s, width(FAMILY)), 5);
+    }
+
+    #[test]
+    fn split_to_width_moves_a_wide_grapheme_at_the_edge() {
+        assert_eq!(split_to_width("ab漢c", 3, 3), vec!["ab", "漢c"]);
+        assert_eq!(split_to_width("abcd", 1, 3), vec!["a", "bcd"]);
+        assert_eq!(split_to_width("", 3, 3), vec![""]);
+    }
+
+    #[test]
+    fn split_to_width_gives_a_too_wide_grapheme_its_own_line() {
+        assert_eq!(split_to_width("漢漢", 1, 1), vec!["漢", "漢"]);
+        let s = format!("x{}y", FAMILY);
+        assert_eq!(split_to_width(&s, 2, 4), vec!["x", FAMILY, "y"]);
+    }
+
+    #[test]
+    fn truncate_adds_an_ellipsis_only_when_it_cuts() {
+        assert_eq!(truncate("hello", 5), "hello");
+        assert_eq!(truncate("hello world", 5), "hell…");
+        // 漢 would straddle the edge, so it goes too.
+        assert_eq!(truncate("a漢漢", 3), "a…");
+        assert_eq!(truncate("漢字漢字", 5), "漢字…");
+        assert_eq!(truncate(&format!("{}{}", FAMILY, FAMILY), 7), format!("{}…", FAMILY));
+    }
+
+    #[test]
+    fn truncate_to_one_column_or_none() {
+        assert_eq!(truncate("hello", 1), "…");
+        assert_eq!(truncate("漢", 1), "…");
+        assert_eq!(truncate("hello", 0), "");
+        assert_eq!(truncate("", 0), "");
+    }
+
+    #[test]
+    fn tail_keeps_whole_graphemes_from_the_end() {
+        assert_eq!(tail("abc漢", 3), "c漢");
+        assert_eq!(tail("abc漢", 1), "");
+        assert_eq!(tail("abc", 10), "abc");
+        assert_eq!(tail("שלום", 2), "ום");
+        let s = format!("ab{}", FAMILY);
+        assert_eq!(tail(&s, width(FAMILY)), FAMILY);
+        assert_eq!(tail(&s, width(FAMILY) - 1), "");
+    }
+}
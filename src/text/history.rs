@@ -0,0 +1,164 @@
+// undo/redo history for an EditArea.
+//
+// The history doesn't know anything about the buffer it tracks; it just records which characters
+// went in or out of it and where.  The EditArea replays those changes against its GapBuffer using
+// the regular put/back/seek operations.
+
+// how many undo steps to remember before forgetting the oldest ones.
+const HISTORY_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    // characters inserted starting at a position.
+    Insert(usize, Vec<char>),
+    // characters removed starting at a position.
+    Remove(usize, Vec<char>),
+}
+
+impl Change {
+    // where the cursor ends up after this change is applied.
+    pub fn cursor_after(&self) -> usize {
+        match self {
+            Change::Insert(at, text) => at + text.len(),
+            Change::Remove(at, _) => *at,
+        }
+    }
+    // the change that cancels this one out.
+    pub fn inverse(&self) -> Change {
+        match self {
+            Change::Insert(at, text) => Change::Remove(*at, text.clone()),
+            Change::Remove(at, text) => Change::Insert(*at, text.clone()),
+        }
+    }
+}
+
+// a single undo step: the changes it made, in order, and where the cursor was before them.
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub changes: Vec<Change>,
+    pub cursor: usize,
+}
+
+impl Step {
+    // where the cursor ends up after the whole step is applied.
+    pub fn cursor_after(&self) -> usize {
+        self.changes.last().map_or(self.cursor, |c| c.cursor_after())
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    // can the most recent step still be extended?  typing a run of characters should only take
+    // a single undo to get rid of; any cursor movement or undo/redo closes the run.
+    open: bool,
+    // how deep we are in begin_group/end_group pairs, and whether the group has its step yet.
+    // everything recorded inside a group is one undo step.
+    group: usize,
+    grouped: bool,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.open = false;
+        self.group = 0;
+        self.grouped = false;
+    }
+    // stop merging new changes into the most recent step.
+    pub fn seal(&mut self) {
+        self.open = false;
+    }
+    // start recording changes into a single step, until the matching end_group.  groups nest.
+    pub fn begin_group(&mut self) {
+        if self.group == 0 {
+            self.grouped = false;
+        }
+        self.group += 1;
+    }
+    pub fn end_group(&mut self) {
+        self.group = self.group.saturating_sub(1);
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    // record a change that was just made to the buffer.  `cursor` is where the cursor was before
+    // the change.  `mergeable` changes (single keypresses) are grouped with the previous step if
+    // they continue it.
+    pub fn record(&mut self, change: Change, cursor: usize, mergeable: bool) {
+        self.redo.clear();
+        if self.group > 0 && self.grouped {
+            if let Some(last) = self.undo.last_mut() {
+                last.changes.push(change);
+                return;
+            }
+        }
+        if mergeable && self.open && self.group == 0 {
+            if let Some(last) = self.undo.last_mut().and_then(|s| s.changes.last_mut()) {
+                if merge(last, &change) {
+                    return;
+                }
+            }
+        }
+        self.grouped = self.group > 0;
+        self.undo.push(Step {
+            changes: vec![change],
+            cursor,
+        });
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.open = mergeable && !self.grouped;
+    }
+    // pop the most recent step to undo; the caller applies the inverse of its changes, last first.
+    pub fn undo(&mut self) -> Option<Step> {
+        self.open = false;
+        let step = self.undo.pop()?;
+        self.redo.push(step.clone());
+        Some(step)
+    }
+    // pop the most recently undone step; the caller applies its changes again.
+    pub fn redo(&mut self) -> Option<Step> {
+        self.open = false;
+        let step = self.redo.pop()?;
+        self.undo.push(step.clone());
+        Some(step)
+    }
+}
+
+// try to fold `next` into `last`.  returns false if they aren't contiguous.
+fn merge(last: &mut Change, next: &Change) -> bool {
+    match (last, next) {
+        // typing: abc| then d => abcd|
+        (Change::Insert(at, text), Change::Insert(next_at, next_text))
+            if *at + text.len() == *next_at =>
+        {
+            text.extend_from_slice(next_text);
+            true
+        }
+        // backspacing: abc| then back => ab|, the removed text grows to the left
+        (Change::Remove(at, text), Change::Remove(next_at, next_text))
+            if *next_at + next_text.len() == *at =>
+        {
+            let mut joined = next_text.clone();
+            joined.append(text);
+            *text = joined;
+            *at = *next_at;
+            true
+        }
+        // deleting: |abc then delete => |bc, the removed text grows to the right
+        (Change::Remove(at, text), Change::Remove(next_at, next_text)) if *at == *next_at => {
+            text.extend_from_slice(next_text);
+            true
+        }
+        _ => false,
+    }
+}
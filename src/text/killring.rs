@@ -0,0 +1,33 @@
+use std::collections::VecDeque;
+
+// how many kills to remember.
+const KILL_RING_SIZE: usize = 30;
+
+// A kill ring, like emacs has: every cut or copy is pushed onto the front, and pasting takes the
+// most recent one.
+#[derive(Default, Debug, Clone)]
+pub struct KillRing {
+    ring: VecDeque<String>,
+}
+
+impl KillRing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, s: String) {
+        if s.is_empty() {
+            return;
+        }
+        if self.ring.len() >= KILL_RING_SIZE {
+            self.ring.pop_back();
+        }
+        self.ring.push_front(s);
+    }
+    // the most recent kill.
+    pub fn yank(&self) -> Option<&str> {
+        self.ring.front().map(|s| s.as_ref())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+}
@@ -1,4 +1,7 @@
 pub mod gapbuffer;
+pub mod grapheme;
 pub mod editarea;
 pub mod editcursor;
+pub mod history;
+pub mod killring;
 pub mod word;
@@ -1,6 +1,5 @@
-
 // from tui-textarea.rs
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 enum CharKind {
     Whitespace,
     Punctuation,
@@ -23,36 +22,75 @@ impl HasCharKind for char {
     }
 }
 
-// given a line and a position of the cursor in it, find the next word.
+// All positions here are character positions, not byte positions.  A "word" is a run of
+// characters of the same kind: letters and digits, or punctuation.
+
+// given a line and a position of the cursor in it, find the start of the next word.
 pub fn next_word(line: &str, current: usize) -> Option<usize> {
-    // a_bcd cursor at 1
-    // skip 1 element
-    let mut line_itr = line.chars().enumerate().skip(current);
-    let mut prev_ch_kind = line_itr.next()?.1.kind();
-    for (pos, ch) in line_itr {
-        // if cursor is on whitespace and next thing is whitespace, keep moving
-        let ch_kind = ch.kind();
-        if ch_kind != CharKind::Other && prev_ch_kind == ch_kind {
-            return Some(pos);
+    let chars: Vec<char> = line.chars().collect();
+    let mut pos = current;
+    let kind = chars.get(pos)?.kind();
+    // skip the rest of the word we're on, then the whitespace after it.
+    if kind != CharKind::Whitespace {
+        while pos < chars.len() && chars[pos].kind() == kind {
+            pos += 1;
         }
-        prev_ch_kind = ch_kind;
     }
-    None
+    while pos < chars.len() && chars[pos].kind() == CharKind::Whitespace {
+        pos += 1;
+    }
+    if pos < chars.len() {
+        Some(pos)
+    } else {
+        None
+    }
+}
+
+// given a line and a position of the cursor in it, find the start of the word before it, or the
+// start of the word the cursor is in the middle of.
+pub fn prev_word(line: &str, current: usize) -> Option<usize> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut pos = usize::min(current, chars.len());
+    // skip the whitespace before the cursor, then the word before that.
+    while pos > 0 && chars[pos - 1].kind() == CharKind::Whitespace {
+        pos -= 1;
+    }
+    if pos == 0 {
+        return None;
+    }
+    let kind = chars[pos - 1].kind();
+    while pos > 0 && chars[pos - 1].kind() == kind {
+        pos -= 1;
+    }
+    Some(pos)
+}
+
+// given a line and a position of the cursor in it, find the last character of the current word,
+// or of the next one if the cursor is already at the end of a word.
+pub fn word_end(line: &str, current: usize) -> Option<usize> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut pos = current + 1;
+    while pos < chars.len() && chars[pos].kind() == CharKind::Whitespace {
+        pos += 1;
+    }
+    let kind = chars.get(pos)?.kind();
+    while pos + 1 < chars.len() && chars[pos + 1].kind() == kind {
+        pos += 1;
+    }
+    Some(pos)
 }
 
-// TODO check if this works.
-// pub fn prev_word(line: &str, current: usize) -> Option<usize> {
-//     // a_bcd cursor at 1
-//     // skip 1 element
-//     let mut line_itr = line.chars().rev().enumerate().skip(line.len() - current);
-//     let mut prev_ch_kind = line_itr.next()?.1.kind();
-//     for (pos, ch) in line_itr {
-//         // if cursor is on whitespace and next thing is whitespace, keep moving
-//         let ch_kind = ch.kind();
-//         if ch_kind != CharKind::Other && prev_ch_kind == ch_kind {
-//             return Some(pos);
-//         }
-//         prev_ch_kind = ch_kind;
-//     }
-//     None
-// }
+// given a line and a position of the cursor in it, find the position just past the end of the
+// word the cursor is in, or of the next one if the cursor isn't in a word.
+pub fn next_word_end(line: &str, current: usize) -> Option<usize> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut pos = current;
+    while pos < chars.len() && chars[pos].kind() == CharKind::Whitespace {
+        pos += 1;
+    }
+    let kind = chars.get(pos)?.kind();
+    while pos < chars.len() && chars[pos].kind() == kind {
+        pos += 1;
+    }
+    Some(pos)
+}
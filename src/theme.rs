@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::BorderType;
+
+// The built-in themes.  Everything in one can be changed from the config.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    // for dark terminals; what the app always looked like.
+    #[default]
+    Default,
+    // for light terminals, where yellow and light grey disappear.
+    Light,
+    // bright, bold, and thick borders.
+    HighContrast,
+    // no colours at all, only bold, reverse and friends.  also used when NO_COLOR is set.
+    Monochrome,
+}
+
+// What the config says about the theme, e.g.
+//   [theme]
+//   name = "light"
+//   [theme.styles]
+//   write = "magenta bold"
+//   highlight = "black on yellow"
+//   border_type = "double"
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub name: ThemeName,
+    pub styles: BTreeMap<String, String>,
+}
+
+// The styles the screens draw with.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    // the boxes around the message view, the input bar and the lists.
+    pub border: Style,
+    pub border_type: BorderType,
+    // the selected message or mru entry, and selected text in the input bar.
+    pub highlight: Style,
+    // the mode in the status bar.
+    pub normal: Style,
+    pub write: Style,
+    pub edit: Style,
+    // the clock on top of the message view.
+    pub timestamp: Style,
+    pub elphy: Style,
+    pub message_bar: Style,
+    // the descriptions in the hint bar; the keys themselves are always bold.
+    pub hint: Style,
+    // things that want attention, like new messages below the view.
+    pub notice: Style,
+    // markdown in messages: `code`, [links](...) and > quotes.
+    pub code: Style,
+    pub link: Style,
+    pub quote: Style,
+    // ``` code blocks: the box they sit in, and the highlighting inside.
+    pub code_block: Style,
+    pub keyword: Style,
+    pub string: Style,
+    pub comment: Style,
+    pub number: Style,
+    // everything that went wrong reading the config's styles.
+    pub warnings: Vec<String>,
+}
+
+impl Theme {
+    // the named theme with the config's styles laid over it.  with `no_color`, the theme is
+    // monochrome no matter what, and colours in the config are dropped.
+    pub fn load(config: &ThemeConfig, no_color: bool) -> Self {
+        let name = if no_color {
+            ThemeName::Monochrome
+        } else {
+            config.name
+        };
+        let mut theme = Theme::builtin(name);
+        for (key, value) in &config.styles {
+            if key == "border_type" {
+                match parse_border_type(value) {
+                    Some(border_type) => theme.border_type = border_type,
+                    None => theme.warnings.push(format!(
+                        "theme: \"{}\" isn't a border type; try plain, rounded, double or thick",
+                        value
+                    )),
+                }
+                continue;
+            }
+            let style = match parse_style(value) {
+                Ok(style) if no_color => Style {
+                    fg: None,
+                    bg: None,
+                    ..style
+                },
+                Ok(style) => style,
+                Err(e) => {
+                    theme
+                        .warnings
+                        .push(format!("theme: {} in {} = \"{}\"", e, key, value));
+                    continue;
+                }
+            };
+            match theme.style_mut(key) {
+                Some(slot) => *slot = style,
+                None => theme
+                    .warnings
+                    .push(format!("theme: there is no \"{}\" style", key)),
+            }
+        }
+        theme
+    }
+    pub fn builtin(name: ThemeName) -> Self {
+        let plain = Style::default();
+        let fg = |color| Style::default().fg(color);
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let reversed = Style::default().add_modifier(Modifier::REVERSED);
+        match name {
+            ThemeName::Default => Theme {
+                border: plain,
+                border_type: BorderType::Rounded,
+                highlight: reversed,
+                normal: fg(Color::Blue),
+                write: fg(Color::Green),
+                edit: fg(Color::Red),
+                timestamp: plain,
+                elphy: plain,
+                message_bar: plain,
+                hint: fg(Color::Gray),
+                notice: fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                code: fg(Color::Cyan),
+                link: fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED),
+                quote: fg(Color::Gray).add_modifier(Modifier::ITALIC),
+                code_block: Style::default().bg(Color::Indexed(235)),
+                keyword: fg(Color::Magenta),
+                string: fg(Color::Green),
+                comment: fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                number: fg(Color::Yellow),
+                warnings: Vec::new(),
+            },
+            ThemeName::Light => Theme {
+                border: fg(Color::DarkGray),
+                border_type: BorderType::Rounded,
+                highlight: reversed,
+                normal: fg(Color::Blue),
+                write: fg(Color::Green),
+                edit: fg(Color::Red),
+                timestamp: fg(Color::DarkGray),
+                elphy: fg(Color::DarkGray),
+                message_bar: fg(Color::Black),
+                hint: fg(Color::DarkGray),
+                notice: fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                code: fg(Color::Magenta),
+                link: fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+                quote: fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                code_block: Style::default().bg(Color::Indexed(254)),
+                keyword: fg(Color::Blue).add_modifier(Modifier::BOLD),
+                string: fg(Color::Green),
+                comment: fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                number: fg(Color::Magenta),
+                warnings: Vec::new(),
+            },
+            ThemeName::HighContrast => Theme {
+                border: fg(Color::White).add_modifier(Modifier::BOLD),
+                border_type: BorderType::Thick,
+                highlight: Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+                normal: fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+                write: fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+                edit: fg(Color::LightRed).add_modifier(Modifier::BOLD),
+                timestamp: fg(Color::White).add_modifier(Modifier::BOLD),
+                elphy: fg(Color::White),
+                message_bar: fg(Color::White).add_modifier(Modifier::BOLD),
+                hint: fg(Color::White),
+                notice: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                code: fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+                link: fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+                quote: fg(Color::White).add_modifier(Modifier::ITALIC),
+                code_block: Style::default().fg(Color::White).bg(Color::Black),
+                keyword: fg(Color::LightMagenta).add_modifier(Modifier::BOLD),
+                string: fg(Color::LightGreen),
+                comment: fg(Color::Gray).add_modifier(Modifier::ITALIC),
+                number: fg(Color::LightYellow),
+                warnings: Vec::new(),
+            },
+            ThemeName::Monochrome => Theme {
+                border: plain,
+                border_type: BorderType::Plain,
+                highlight: reversed,
+                normal: bold,
+                write: bold.add_modifier(Modifier::UNDERLINED),
+                edit: reversed,
+                timestamp: plain,
+                elphy: plain,
+                message_bar: plain,
+                hint: Style::default().add_modifier(Modifier::DIM),
+                notice: bold,
+                code: Style::default().add_modifier(Modifier::DIM),
+                link: Style::default().add_modifier(Modifier::UNDERLINED),
+                quote: Style::default().add_modifier(Modifier::ITALIC),
+                code_block: reversed,
+                keyword: bold,
+                string: plain,
+                comment: Style::default().add_modifier(Modifier::ITALIC),
+                number: plain,
+                warnings: Vec::new(),
+            },
+        }
+    }
+    fn style_mut(&mut self, key: &str) -> Option<&mut Style> {
+        Some(match key {
+            "border" => &mut self.border,
+            "highlight" => &mut self.highlight,
+            "normal" => &mut self.normal,
+            "write" => &mut self.write,
+            "edit" => &mut self.edit,
+            "timestamp" => &mut self.timestamp,
+            "elphy" => &mut self.elphy,
+            "message_bar" => &mut self.message_bar,
+            "hint" => &mut self.hint,
+            "notice" => &mut self.notice,
+            "code" => &mut self.code,
+            "link" => &mut self.link,
+            "quote" => &mut self.quote,
+            "code_block" => &mut self.code_block,
+            "keyword" => &mut self.keyword,
+            "string" => &mut self.string,
+            "comment" => &mut self.comment,
+            "number" => &mut self.number,
+            _ => return None,
+        })
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::builtin(ThemeName::Default)
+    }
+}
+
+// is NO_COLOR set?  (https://no-color.org: any value but an empty one counts.)
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+// a style is a list of words: colours, modifiers, and "on" before a background colour, like
+// "bold yellow on blue".  "none" is the terminal's own style.
+pub fn parse_style(s: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut words = s.split_whitespace();
+    while let Some(word) = words.next() {
+        let word = word.to_lowercase();
+        let modifier = match word.as_str() {
+            "none" | "plain" => continue,
+            "bold" => Modifier::BOLD,
+            "dim" => Modifier::DIM,
+            "italic" => Modifier::ITALIC,
+            "underlined" | "underline" => Modifier::UNDERLINED,
+            "reversed" | "reverse" => Modifier::REVERSED,
+            "crossed" | "strikethrough" => Modifier::CROSSED_OUT,
+            "on" => {
+                let color = words.next().ok_or("\"on\" needs a colour after it")?;
+                style = style.bg(parse_color(color)?);
+                continue;
+            }
+            _ => {
+                style = style.fg(parse_color(&word)?);
+                continue;
+            }
+        };
+        style = style.add_modifier(modifier);
+    }
+    Ok(style)
+}
+
+// a colour name, a #rrggbb hex code, or a number from the terminal's 256 colour palette.
+fn parse_color(s: &str) -> Result<Color, String> {
+    let s = s.to_lowercase();
+    let color = match s.replace(['-', '_'], "").as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => {
+            if let Some(hex) = s.strip_prefix('#') {
+                match u32::from_str_radix(hex, 16) {
+                    Ok(rgb) if hex.len() == 6 => {
+                        Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+                    }
+                    _ => return Err(format!("\"{}\" isn't a #rrggbb colour", s)),
+                }
+            } else if let Ok(index) = s.parse::<u8>() {
+                Color::Indexed(index)
+            } else {
+                return Err(format!("\"{}\" isn't a colour", s));
+            }
+        }
+    };
+    Ok(color)
+}
+
+fn parse_border_type(s: &str) -> Option<BorderType> {
+    match s.to_lowercase().as_str() {
+        "plain" => Some(BorderType::Plain),
+        "rounded" => Some(BorderType::Rounded),
+        "double" => Some(BorderType::Double),
+        "thick" => Some(BorderType::Thick),
+        _ => None,
+    }
+}
@@ -3,7 +3,6 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use textwrap;
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Alignment, Constraint, Corner, Direction, Layout, Rect},
@@ -17,45 +16,65 @@ use tui::{
 };
 
 use crate::app::app::*;
+use crate::keymap::action::Action;
+use crate::text::editarea::EditArea;
+use crate::text::grapheme;
+use crate::ui::markdown;
 use crate::util::*;
 
+// below this height, the edit screen drops its margins and the hint bar.
+const ROOMY_HEIGHT: u16 = 16;
+
 pub fn edit_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     let area = f.size();
 
-    let input = if app.edit.is_some() && app.mode == EditorMode::Editing {
-        app.edit.as_ref().unwrap().get()
-    } else {
-        app.input.to_string()
+    let input_area = match &app.edit {
+        Some(edit) if app.mode == EditorMode::Editing => edit.input(),
+        _ => &app.input,
     };
+    let input = input_area.to_string();
 
     // calculate the height of the input bar first!  we will need it when making the layouts.
-    let input_wrap = textwrap::wrap(
-        input.as_ref(),
-        area.width.checked_sub(2).unwrap_or(1) as usize,
-    );
+    let input_width = area.width.checked_sub(2).unwrap_or(1) as usize;
+    let input_wrap = wrap_with_offsets(&input, usize::max(1, input_width));
     let input_line_count = usize::max(1, input_wrap.len());
 
     let max_height = (area.height as f32 * 0.4).ceil() as usize;
 
-    // decide what goes into the displayed input string by using only the last max_height lines.
-    let input_str = input_wrap
-        .iter()
-        .rev()
-        .take(max_height)
-        .map(|x| x.as_ref())
-        .rev()
-        .collect::<Vec<&str>>()
-        .join("\n");
-
     let message_bar_height = 1; // TODO: later adapt the message bar to be variable size
+    // on a short terminal, the hints and the margins are the first things to go.
+    let roomy = area.height >= ROOMY_HEIGHT;
+    let hint_bar_height = if app.config.hint_bar && roomy { 1 } else { 0 };
     let input_bar_height = usize::min(2 + input_line_count, max_height) as u16;
-    let vertical_margin = 1;
+
+    // decide what goes into the displayed input by scrolling just far enough to keep the line
+    // with the cursor in view.
+    let visible_lines = usize::max(1, (input_bar_height as usize).saturating_sub(2));
+    let cursor = input_area.cursor();
+    let cursor_line = input_wrap
+        .iter()
+        .rposition(|(start, _)| *start <= cursor)
+        .unwrap_or(0);
+    let first_line = (cursor_line + 1).saturating_sub(visible_lines);
+    // the cursor goes by screen columns, which wide characters take two of.
+    let cursor_col = input_wrap
+        .get(cursor_line)
+        .map(|(start, line)| grapheme::column(line, cursor - start))
+        .unwrap_or(0);
+    let input_lines: Vec<Spans> = input_wrap
+        .iter()
+        .skip(first_line)
+        .take(visible_lines)
+        .map(|(start, line)| selected_spans(*start, line, input_area, app.theme.highlight))
+        .collect();
+    let vertical_margin = if roomy { 1 } else { 0 };
     let file_view_height = {
         || {
             area.height
                 .checked_sub(2 * vertical_margin)?
                 .checked_sub(input_bar_height)?
                 .checked_sub(1)? // from status bar
+                .checked_sub(hint_bar_height)?
                 .checked_sub(message_bar_height) // from message bar
         }
     }()
@@ -68,6 +87,7 @@ pub fn edit_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
             [
                 Constraint::Length(file_view_height),
                 Constraint::Length(input_bar_height),
+                Constraint::Length(hint_bar_height),
                 Constraint::Length(1),
                 Constraint::Length(message_bar_height),
             ]
@@ -77,80 +97,192 @@ pub fn edit_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
 
     // ==== MESSAGE VIEW ====
 
+    // the padding inside the message view shrinks down to just the border when space is short.
     let message_chunk = Layout::default()
-        .horizontal_margin(3)
-        .vertical_margin(2)
+        .horizontal_margin(if chunks[0].width >= 40 { 3 } else { 1 })
+        .vertical_margin(if chunks[0].height >= 8 { 2 } else { 1 })
         .constraints([Constraint::Percentage(100)].as_ref())
         // .split(f.size()); generates an interesting effect; you can draw widgets over others!
         .split(chunks[0]);
 
     // TODO add dates to each thing on option; remember to convert from file-stored Utc to Local
-    let mut msg_vec = Vec::new();
-    for msg in app.file.messages.iter().rev() {
-        let mut m = textwrap::fill(
-            msg.most_recent()
-                .expect("expected the msg to have an actual commit...")
-                .data(),
-            message_chunk[0].width as usize,
-        );
-        m.push('\n');
-        msg_vec.push(ListItem::new(m));
+    // every line of every message, oldest at the top, tagged with the message's place in the
+    // selection order (0 is the newest).
+    let view = message_chunk[0];
+    let msg_count = app.file.messages.len();
+    let mut lines: Vec<(usize, Spans)> = Vec::new();
+    for (i, msg) in app.file.messages.iter().enumerate() {
+        let data = msg
+            .most_recent()
+            .expect("expected the msg to have an actual commit...")
+            .data();
+        for line in markdown::render(data, view.width as usize, &app.theme) {
+            lines.push((msg_count - i - 1, line));
+        }
+    }
+
+    // the view is a window of `height` rows ending `scroll` rows above the bottom.
+    let height = view.height as usize;
+    let selected = app.select_state.selected();
+    if app.view.reveal {
+        // scroll just far enough to get the newly selected message in view, top first.
+        if let Some(sel) = selected {
+            let first = lines.iter().position(|(i, _)| *i == sel);
+            let last = lines.iter().rposition(|(i, _)| *i == sel);
+            if let (Some(first), Some(last)) = (first, last) {
+                let mut bottom = lines.len() - usize::min(app.view.scroll, lines.len());
+                if last + 1 > bottom {
+                    bottom = last + 1;
+                }
+                if first < bottom.saturating_sub(height) {
+                    bottom = usize::min(first + height, lines.len());
+                }
+                app.view.scroll = lines.len() - bottom;
+            }
+        }
+        app.view.reveal = false;
+    }
+    app.view.scroll = usize::min(app.view.scroll, lines.len().saturating_sub(height));
+    if app.view.scroll == 0 {
+        app.view.unseen = 0;
+    }
+    app.view.height = height;
+    app.view.width = view.width as usize;
+    let bottom = lines.len() - app.view.scroll;
+    let top = bottom.saturating_sub(height);
+
+    // like the input bar, messages sit at the bottom of the view when there's room to spare.
+    let pad = height - (bottom - top);
+    let mut msg_lines: Vec<Spans> = vec![Spans::default(); pad];
+    app.mouse.list = view;
+    app.mouse.rows.clear();
+    for (row, (idx, line)) in lines[top..bottom].iter().enumerate() {
+        if Some(*idx) == selected {
+            // pad the line so the whole row lights up.
+            let padding = (view.width as usize).saturating_sub(line.width());
+            let mut highlighted: Vec<Span> = line
+                .0
+                .iter()
+                .map(|span| Span::styled(span.content.clone(), span.style.patch(app.theme.highlight)))
+                .collect();
+            highlighted.push(Span::styled(" ".repeat(padding), app.theme.highlight));
+            msg_lines.push(Spans::from(highlighted));
+        } else {
+            msg_lines.push(line.clone());
+        }
+        // remember which rows each message landed on, for the mouse.
+        let y = view.y + (pad + row) as u16;
+        match app.mouse.rows.last_mut() {
+            Some((_, end, last)) if last == idx => *end = y + 1,
+            _ => app.mouse.rows.push((y, y + 1, *idx)),
+        }
     }
 
     let msg_block = Block::default()
-        .title(current_time_string()) // uses Local, not Utc
+        .title(Span::styled(current_time_string(), app.theme.timestamp)) // uses Local, not Utc
         .borders(Borders::ALL)
-        .border_type(BorderType::Rounded);
-
-    let msg_widget = List::new(msg_vec)
-        .block(
-            Block::default()
-                .borders(Borders::NONE)
-                .border_type(BorderType::Rounded),
-        )
-        .style(Style::default())
-        .highlight_style(Style::default().add_modifier(Modifier::REVERSED)) // Modifier::REVERSED means reversed colors, not reversed text.
-        .start_corner(Corner::BottomLeft);
+        .border_style(app.theme.border)
+        .border_type(app.theme.border_type);
 
     f.render_widget(msg_block, chunks[0]);
-    f.render_stateful_widget(msg_widget, message_chunk[0], &mut app.select_state);
+    f.render_widget(Paragraph::new(msg_lines), view);
+
+    // the scrollbar sits on the right border, level with the messages.
+    if lines.len() > height && height > 0 {
+        let thumb_len = usize::max(1, height * height / lines.len());
+        let thumb_start = usize::min(top * height / lines.len(), height - thumb_len);
+        let thumb = Rect {
+            x: chunks[0].right().saturating_sub(1),
+            y: view.y + thumb_start as u16,
+            width: 1,
+            height: thumb_len as u16,
+        };
+        let thumb_lines = vec![Spans::from(Span::styled("┃", app.theme.border)); thumb_len];
+        f.render_widget(Paragraph::new(thumb_lines), thumb);
+    }
+
+    // messages that came in below while scrolled up get a note on the bottom border.
+    if app.view.unseen > 0 {
+        let note = format!(" ↓ {} new ", app.view.unseen);
+        let width = u16::min(note.chars().count() as u16, chunks[0].width.saturating_sub(4));
+        let note_area = Rect {
+            x: chunks[0].right().saturating_sub(width + 2),
+            y: chunks[0].bottom().saturating_sub(1),
+            width,
+            height: 1,
+        };
+        f.render_widget(
+            Paragraph::new(Span::styled(note, app.theme.notice)),
+            note_area,
+        );
+    }
 
     // ==== INPUT BAR ====
 
     let input_title = match app.mode {
+        EditorMode::Writing | EditorMode::Editing if app.vi.is_normal() => "-- NORMAL -- ",
+        EditorMode::Normal if app.read_only => "Read-only",
         EditorMode::Normal => "Type here",
         EditorMode::Writing => "Typing... ",
         EditorMode::Editing => "Editing... ",
         EditorMode::Saving => "Saving!",
+        EditorMode::Exporting => "Exporting!",
     };
 
     // TODO: store scroll state of this input.
     // scroll state is rows, cols
-    let input_bar = Paragraph::new(input_str).scroll((0, 0)).block(
+    let input_bar = Paragraph::new(input_lines).scroll((0, 0)).block(
         Block::default()
             .title(input_title)
             .borders(Borders::ALL)
-            .border_type(BorderType::Rounded),
+            .border_style(app.theme.border)
+            .border_type(app.theme.border_type),
     );
     f.render_widget(input_bar, chunks[1]);
+    app.mouse.input = Rect {
+        x: chunks[1].x + 1,
+        y: chunks[1].y + 1,
+        width: chunks[1].width.saturating_sub(2),
+        height: chunks[1].height.saturating_sub(2),
+    };
+    app.mouse.input_lines = input_wrap
+        .iter()
+        .skip(first_line)
+        .take(visible_lines)
+        .cloned()
+        .collect();
 
-    // TODO: put cursor at the end of the input
-    // you're going to need to store a cursor state, and maybe a scroll state?
+    // only show the cursor when there's something to type into.
     match app.mode {
         EditorMode::Normal => {}
-        EditorMode::Writing => {}
-        EditorMode::Editing => {}
-        EditorMode::Saving => {}
+        EditorMode::Writing | EditorMode::Editing => {
+            let inner_width = chunks[1].width.saturating_sub(2);
+            f.set_cursor(
+                chunks[1].x + 1 + u16::min(cursor_col as u16, inner_width),
+                chunks[1].y + 1 + (cursor_line - first_line) as u16,
+            );
+        }
+        EditorMode::Saving | EditorMode::Exporting => {}
+    }
+
+    // ==== HINT BAR ====
+    if app.config.hint_bar {
+        let hint_chunk = Layout::default()
+            .horizontal_margin(2)
+            .constraints([Constraint::Percentage(100)].as_ref())
+            .split(chunks[2]);
+        f.render_widget(Paragraph::new(hint_spans(app)), hint_chunk[0]);
     }
 
     // ==== STATUS BAR ====
 
     // TODO: show text: like what mode, what file name, whether editing or writing... etc.
-    let (mode_text, mode_color) = match app.mode {
-        EditorMode::Normal => ("NORMAL", Color::Blue),
-        EditorMode::Writing => ("WRITE", Color::Green),
-        EditorMode::Editing => ("EDIT", Color::Red),
-        EditorMode::Saving => ("NORMAL", Color::Blue),
+    let (mode_text, mode_style) = match app.mode {
+        EditorMode::Normal if app.read_only => ("READ-ONLY", app.theme.normal),
+        EditorMode::Normal => ("NORMAL", app.theme.normal),
+        EditorMode::Writing => ("WRITE", app.theme.write),
+        EditorMode::Editing => ("EDIT", app.theme.edit),
+        EditorMode::Saving | EditorMode::Exporting => ("NORMAL", app.theme.normal),
     };
 
     // TODO: make this a spans and calculate the spaces needed to right-justify the file name on
@@ -159,9 +291,9 @@ pub fn edit_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         .direction(Direction::Horizontal)
         .horizontal_margin(2)
         .constraints([Constraint::Min(10), Constraint::Percentage(100)].as_ref())
-        .split(chunks[2]);
+        .split(chunks[3]);
 
-    let status_bar_mode = Paragraph::new(Span::styled(mode_text, Style::default().fg(mode_color)))
+    let status_bar_mode = Paragraph::new(Span::styled(mode_text, mode_style))
         .block(Block::default().borders(Borders::NONE));
     let status_bar_title = Paragraph::new(if app.file.name.is_empty() {
         Span::styled("NEW", Style::default().add_modifier(Modifier::BOLD))
@@ -179,11 +311,11 @@ pub fn edit_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     // ==== MESSAGE BAR ====
     // TODO: create a message bar, like in Vim, that expands as the status message
     // grows.  Put it beneath the current status bar
-    let status_message = Paragraph::new(app.status_msg.as_ref());
-    f.render_widget(status_message, chunks[3]);
+    let status_message = Paragraph::new(app.status_msg.as_ref()).style(app.theme.message_bar);
+    f.render_widget(status_message, chunks[4]);
 
-    // ==== SAVE-AS POPUP WINDOW ====
-    if app.mode == EditorMode::Saving {
+    // ==== SAVE-AS AND EXPORT POPUP WINDOW ====
+    if matches!(app.mode, EditorMode::Saving | EditorMode::Exporting) {
         let center_col = Layout::default()
             .direction(Direction::Horizontal)
             .constraints(
@@ -216,8 +348,13 @@ pub fn edit_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
 
         let popup = Block::default()
             .borders(Borders::ALL)
+            .border_style(app.theme.border)
             .border_type(BorderType::Double);
-        let note = Paragraph::new("Enter file name: ")
+        let note = Paragraph::new(if app.mode == EditorMode::Exporting {
+            "Export to (.md, .html or .json): "
+        } else {
+            "Enter file name: "
+        })
             .block(Block::default().borders(Borders::BOTTOM).border_type(BorderType::Plain))
             .alignment(Alignment::Center);
         let filename = app.temp_input.clone();
@@ -230,3 +367,282 @@ pub fn edit_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     }
 }
 
+
+// wrap `text` the same way textwrap does, but keep track of which character each line starts at,
+// so that the cursor and the selection can be found in the wrapped lines.
+fn wrap_with_offsets(text: &str, width: usize) -> Vec<(usize, String)> {
+    let mut byte = 0;
+    let mut chars = 0;
+    textwrap::wrap(text, width)
+        .into_iter()
+        .map(|line| {
+            // every wrapped line is a piece of the original text; whatever textwrap dropped
+            // between them is just whitespace and line breaks.
+            // an empty line is always right after a line break.
+            let found = if line.is_empty() && byte > 0 {
+                text[byte..].find('\n').map_or(byte, |i| byte + i + 1)
+            } else {
+                text[byte..].find(line.as_ref()).map_or(byte, |i| byte + i)
+            };
+            chars += text[byte..found].chars().count();
+            let start = chars;
+            byte = found + line.len();
+            chars += line.chars().count();
+            (start, line.into_owned())
+        })
+        .collect()
+}
+
+// split a wrapped line of the input into spans, with the selected part highlighted.
+fn selected_spans(
+    start: usize,
+    line: &str,
+    input: &EditArea,
+    highlight: Style,
+) -> Spans<'static> {
+    let (sel_start, sel_end) = match input.selection() {
+        Some(selection) => selection,
+        None => return Spans::from(line.to_string()),
+    };
+    let len = line.chars().count();
+    let from = sel_start.saturating_sub(start).min(len);
+    let to = sel_end.saturating_sub(start).min(len);
+    let piece = |a: usize, b: usize| line.chars().skip(a).take(b - a).collect::<String>();
+    Spans::from(vec![
+        Span::raw(piece(0, from)),
+        Span::styled(piece(from, to), highlight),
+        Span::raw(piece(to, len)),
+    ])
+}
+
+// the few keys worth knowing right now, e.g. "e edit selected · Esc unselect · h help".  they're
+// looked up in the live keymap, so rebinding a key changes its hint too.
+fn hint_spans(app: &App) -> Spans<'static> {
+    let context = app.context_of(AppRoute::Edit);
+    let hints: Vec<(String, &'static str)> = if app.vi.is_normal()
+        && matches!(app.mode, EditorMode::Writing | EditorMode::Editing)
+    {
+        // vi's normal mode keys don't live in the keymap.
+        vec![
+            ("i".to_string(), "insert"),
+            ("u".to_string(), "undo"),
+            ("Esc".to_string(), "leave"),
+        ]
+    } else {
+        let actions: &[Action] = match app.mode {
+            EditorMode::Normal if app.select_state.selected().is_some() => &[
+                Action::Edit,
+                Action::Unselect,
+                Action::Insert,
+                Action::Help,
+            ],
+            EditorMode::Normal => &[
+                Action::Insert,
+                Action::SelectUp,
+                Action::Save,
+                Action::Help,
+                Action::Quit,
+            ],
+            EditorMode::Writing | EditorMode::Editing => &[
+                Action::Commit,
+                Action::Leave,
+                Action::Undo,
+                Action::Paste,
+            ],
+            EditorMode::Saving | EditorMode::Exporting => &[Action::Confirm, Action::Cancel],
+        };
+        let bindings = app.keymap.bindings(context);
+        actions
+            .iter()
+            .filter_map(|action| {
+                let (chord, _) = bindings.iter().find(|(_, bound)| bound == action)?;
+                Some((chord.to_string(), action.description()))
+            })
+            .collect()
+    };
+
+    let mut spans = Vec::new();
+    for (i, (key, description)) in hints.into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" · ", app.theme.hint.add_modifier(Modifier::DIM)));
+        }
+        spans.push(Span::styled(key, Style::default().add_modifier(Modifier::BOLD)));
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(description, app.theme.hint));
+    }
+    Spans::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DiaryConfig;
+    use crate::ui::screen_text;
+    use tui::backend::TestBackend;
+
+    // an app on its defaults, with the hint bar on so it can be seen to come and go.
+    fn app() -> App {
+        let mut config = DiaryConfig::in_memory();
+        config.hint_bar = true;
+        App::from_config(config, Vec::new())
+    }
+
+    // the edit screen, with the clock in the message view's title zeroed out.
+    fn render(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| edit_screen(f, app)).unwrap();
+        screen_text(terminal.backend())
+            .into_iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| if c.is_ascii_digit() { '0' } else { c })
+                    .collect::<String>()
+                    .replace("PM", "AM")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fits_the_minimum_size() {
+        assert_eq!(
+            render(&mut app(), 20, 8),
+            vec![
+                "╭0000-00-00 00:00:0╮",
+                "│                  │",
+                "╰──────────────────╯",
+                "╭Type here─────────╮",
+                "│                  │",
+                "╰──────────────────╯",
+                "  NORMAL       NEW  ",
+                "                    ",
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_the_margins_and_hints_just_below_roomy() {
+        assert_eq!(
+            render(&mut app(), 30, ROOMY_HEIGHT - 1),
+            vec![
+                "╭0000-00-00 00:00:00 AM──────╮",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "╰────────────────────────────╯",
+                "╭Type here───────────────────╮",
+                "│                            │",
+                "╰────────────────────────────╯",
+                "  NORMAL                 NEW  ",
+                "                              ",
+            ]
+        );
+    }
+
+    #[test]
+    fn has_margins_and_hints_once_roomy() {
+        assert_eq!(
+            render(&mut app(), 30, ROOMY_HEIGHT),
+            vec![
+                "                              ",
+                "╭0000-00-00 00:00:00 AM──────╮",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "│                            │",
+                "╰────────────────────────────╯",
+                "╭Type here───────────────────╮",
+                "│                            │",
+                "╰────────────────────────────╯",
+                "  i write a message · Up up   ",
+                "  NORMAL                 NEW  ",
+                "                              ",
+                "                              ",
+            ]
+        );
+        assert_eq!(
+            render(&mut app(), 40, ROOMY_HEIGHT + 1),
+            vec![
+                "                                        ",
+                "╭0000-00-00 00:00:00 AM────────────────╮",
+                "│                                      │",
+                "│                                      │",
+                "│                                      │",
+                "│                                      │",
+                "│                                      │",
+                "│                                      │",
+                "│                                      │",
+                "╰──────────────────────────────────────╯",
+                "╭Type here─────────────────────────────╮",
+                "│                                      │",
+                "╰──────────────────────────────────────╯",
+                "  i write a message · Up up · w save ·  ",
+                "  NORMAL                           NEW  ",
+                "                                        ",
+                "                                        ",
+            ]
+        );
+    }
+
+    // the rows of text in the input bar.
+    fn input_rows(screen: &[String]) -> usize {
+        let top = screen.iter().position(|row| row.starts_with("╭Type here")).unwrap();
+        screen[top + 1..].iter().take_while(|row| row.starts_with('│')).count()
+    }
+
+    #[test]
+    fn input_bar_grows_but_only_to_two_fifths_of_the_screen() {
+        let mut app = app();
+        app.input.insert_str("one two three four five six seven eight nine ten");
+        // 40% of 8 rows, rounded up, is 4: two rows of text between the borders.
+        let screen = render(&mut app, 20, 8);
+        assert_eq!(input_rows(&screen), 2);
+        assert_eq!(screen[6], "  NORMAL       NEW  ");
+        // on a taller screen, all three of its lines show.
+        assert_eq!(input_rows(&render(&mut app, 20, 24)), 3);
+    }
+
+    #[test]
+    fn every_size_from_the_minimum_up_draws() {
+        for width in 20..=44 {
+            for height in 8..=24 {
+                let screen = render(&mut app(), width, height);
+                assert!(screen.iter().any(|row| row.contains("NORMAL")), "{}x{}", width, height);
+            }
+        }
+    }
+
+    // the message view's rows inside its margins, without the scrollbar on its right border.
+    fn messages(screen: &[String]) -> Vec<String> {
+        screen[2..8]
+            .iter()
+            .map(|row| row.chars().take(29).collect())
+            .collect()
+    }
+
+    #[test]
+    fn a_message_arriving_while_scrolled_up_leaves_the_view_be() {
+        let mut app = app();
+        for i in 0..20 {
+            app.file.push_string(format!("message {}", i));
+        }
+        render(&mut app, 30, ROOMY_HEIGHT - 1);
+        app.view.scroll = 5;
+        let before = messages(&render(&mut app, 30, ROOMY_HEIGHT - 1));
+        // (the digits are masked, but every row is one of the old messages.)
+        assert!(before.iter().all(|row| row.contains("message")));
+        // the fences and the quote take rows that plain wrapping would count differently.
+        let text = "```\nlet x = 1;\n```\n> a long quote that wraps onto another row";
+        app.view.arrived(text, &app.theme);
+        app.file.push_string(text.to_string());
+        let after = messages(&render(&mut app, 30, ROOMY_HEIGHT - 1));
+        assert_eq!(before, after);
+        assert_eq!(app.view.unseen, 1);
+    }
+}
@@ -3,7 +3,6 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use textwrap;
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Alignment, Constraint, Corner, Direction, Layout, Rect},
@@ -17,10 +16,13 @@ use tui::{
 };
 
 use crate::app::app::*;
+use crate::keymap::action::{Action, Context};
+use crate::keymap::{vi, KeymapProfile};
+use crate::text::grapheme;
 use crate::ui::elphy;
 use crate::util::*;
 
-const HELPEPHANT: &'static str = r#"
+const HELPEPHANT: &str = r#"
    Here to                   _.-----.._____,-~~~~-._...__
                           ,-'            /         `....
    help!                ,'             ,'      .  .  \::.
@@ -47,83 +49,252 @@ It's Elphy,         ,'    ..   .     .      .  . : ;':::.
 "#;
 // 58x26
 const MIN_ELPHY_WIDTH: u16 = 35;
+// how wide the key column of the command list is.
+const KEY_COLUMN_WIDTH: usize = 14;
 
-// TODO add the elephant somewhere.
-pub fn help_screen<B: Backend>(f: &mut Frame<B>, _app: &mut App) {
+// The help screen lists what every key does where the user came from, straight from the live
+// keymap, so it can't drift from what the keys actually do.
+pub fn help_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     let rect = f.size();
+    // Elphy only comes along if there's room for both him and the commands.
+    let elphy_width = if rect.width >= 2 * MIN_ELPHY_WIDTH {
+        MIN_ELPHY_WIDTH
+    } else {
+        0
+    };
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .margin(1)
         .constraints(
             [
-                Constraint::Length(rect.width - MIN_ELPHY_WIDTH),
-                Constraint::Min(MIN_ELPHY_WIDTH),
+                Constraint::Length(rect.width.saturating_sub(2 + elphy_width)),
+                Constraint::Length(elphy_width),
             ]
             .as_ref(),
         )
         .split(rect);
 
-    let elphy = Paragraph::new(HELPEPHANT);
+    let elphy = Paragraph::new(HELPEPHANT).style(app.theme.elphy);
     f.render_widget(elphy, chunks[1]);
 
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(chunks[0]);
+
+    // ==== SEARCH BOX ====
+    let search_style = if app.help.searching {
+        Style::default()
+    } else {
+        Style::default().add_modifier(Modifier::DIM)
+    };
+    let search_text = if app.help.searching || !app.help.search.is_empty() {
+        format!("/{}", app.help.search)
+    } else {
+        String::from("/ to search, any other key to go back")
+    };
+    let search_box = Paragraph::new(Span::styled(search_text, search_style)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(app.theme.border)
+            .border_type(app.theme.border_type),
+    );
+    f.render_widget(search_box, left[0]);
+    if app.help.searching {
+        f.set_cursor(
+            left[0].x + 2 + grapheme::width(&app.help.search) as u16,
+            left[0].y + 1,
+        );
+    }
+
+    // ==== COMMAND LIST ====
+    let lines = command_lines(app);
+    // the block's title takes up a line.
+    let height = left[1].height.saturating_sub(1) as usize;
+    let max_scroll = lines.len().saturating_sub(height);
+    app.help.scroll = usize::min(app.help.scroll, max_scroll);
+    let title = if max_scroll > 0 {
+        format!(
+            " COMMAND LIST ({}-{} of {}): ",
+            app.help.scroll + 1,
+            usize::min(app.help.scroll + height, lines.len()),
+            lines.len()
+        )
+    } else {
+        String::from(" COMMAND LIST: ")
+    };
+    let command_list = Paragraph::new(lines)
+        .block(Block::default().title(title).borders(Borders::NONE))
+        .scroll((app.help.scroll as u16, 0));
+    f.render_widget(command_list, left[1]);
+}
+
+// the commands for where the help screen was opened from, then for the rest of that screen's
+// modes, filtered by the search.
+fn command_lines(app: &App) -> Vec<Spans<'static>> {
     // now, just a list of commands. first the bold of the command buttons themselves:
     let command_style = Style::default().add_modifier(Modifier::BOLD);
     let descript_style = Style::default()
         .add_modifier(Modifier::ITALIC)
         .add_modifier(Modifier::DIM);
+    let heading_style = Style::default().add_modifier(Modifier::UNDERLINED);
 
-    let command_table = Table::new(vec![
-        Row::new(vec!["NORMAL"]),
-        Row::new(vec![
-            Cell::from(Spans::from(vec![
-                Span::styled("q", command_style),
-                Span::styled(" quit", descript_style),
-            ])),
-            Cell::from(Spans::from(vec![
-                Span::styled("i", command_style),
-                Span::styled(" insert", descript_style),
-            ])),
-        ]),
-        Row::new(vec![
-            Cell::from(Spans::from(vec![
-                Span::styled("w", command_style),
-                Span::styled(" save", descript_style),
-            ])),
-            Cell::from(Spans::from(vec![
-                Span::styled("W", command_style),
-                Span::styled(" save as", descript_style),
-            ])),
-        ]),
-        Row::new(vec![
-            Cell::from(Spans::from(vec![
-                Span::styled("e", command_style),
-                Span::styled(" edit selected", descript_style),
-            ])),
-            // Cell::from(Spans::from(vec![
-            //     Span::styled("W", command_style),
-            //     Span::styled(" save as", descript_style),
-            // ])),
-        ]).height(2),
-        Row::new(vec!["WRITE/EDIT"]),
-        Row::new(vec![
-            Cell::from(Spans::from(vec![
-                Span::styled("Esc", command_style),
-                Span::styled(" normal mode", descript_style),
-            ])),
-            Cell::from(Spans::from(vec![
-                Span::styled("S-Tab", command_style),
-                Span::styled(" normal mode", descript_style),
-            ])),
-        ]),
-    ])
-    .header(Row::new(vec![""]))
-    .block(
-        Block::default()
-            .title(" COMMAND LIST: ")
-            .borders(Borders::NONE),
-    )
-    // .style(Style::default().fg(Color::Yellow))
-    .widths(&[Constraint::Percentage(50), Constraint::Length(50)]);
+    let origin = app
+        .help_origin()
+        .map(|route| app.context_of(route))
+        .unwrap_or(Context::Start);
+    let edit_contexts = [
+        Context::Normal,
+        Context::Writing,
+        Context::Editing,
+        Context::Saving,
+    ];
+    let mut contexts = vec![origin];
+    if edit_contexts.contains(&origin) {
+        contexts.extend(edit_contexts.iter().filter(|&&c| c != origin));
+    }
+
+    let search = app.help.search.to_lowercase();
+    let matches = |keys: &str, description: &str| {
+        search.is_empty()
+            || keys.to_lowercase().contains(&search)
+            || description.contains(&search)
+    };
 
-    f.render_widget(command_table, chunks[0]);
+    let mut lines = Vec::new();
+    for context in contexts {
+        // one row per action, with every key bound to it.
+        let mut rows: Vec<(Action, Vec<String>)> = Vec::new();
+        for (chord, action) in app.keymap.bindings(context) {
+            match rows.iter_mut().find(|(a, _)| a == action) {
+                Some((_, keys)) => keys.push(chord.to_string()),
+                None => rows.push((*action, vec![chord.to_string()])),
+            }
+        }
+        let rows: Vec<(String, &str)> = rows
+            .into_iter()
+            .map(|(action, keys)| (keys.join(", "), action.description()))
+            .collect();
+        let mut sections = vec![(context.title(), rows)];
+        // the vi layer keeps its own keys.
+        if app.config.keymap == KeymapProfile::Vi
+            && (context == Context::Writing || context == Context::Editing)
+        {
+            let vi_rows = vi::NORMAL_KEYS
+                .iter()
+                .map(|(keys, description)| (keys.to_string(), *description))
+                .collect();
+            let title = if context == Context::Writing {
+                "WRITE (VI NORMAL)"
+            } else {
+                "EDIT (VI NORMAL)"
+            };
+            sections.push((title, vi_rows));
+        }
+
+        for (title, rows) in sections {
+            let rows: Vec<_> = rows
+                .into_iter()
+                .filter(|(keys, description)| matches(keys, description))
+                .collect();
+            if rows.is_empty() {
+                continue;
+            }
+            if !lines.is_empty() {
+                lines.push(Spans::default());
+            }
+            lines.push(Spans::from(Span::styled(title, heading_style)));
+            for (keys, description) in rows {
+                lines.push(Spans::from(vec![
+                    Span::styled(format!("{:width$}", keys, width = KEY_COLUMN_WIDTH), command_style),
+                    Span::styled(description, descript_style),
+                ]));
+            }
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Spans::from(Span::styled("no matching commands", descript_style)));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DiaryConfig;
+    use crate::ui::draw_screen;
+
+    // at the minimum size, narrow but tall, and wide enough for Elphy to come along.
+    #[test]
+    fn help_from_the_start_screen() {
+        let mut app = App::from_config(DiaryConfig::in_memory(), Vec::new());
+        app.routes = vec![AppRoute::Start, AppRoute::Help];
+        assert_eq!(
+            draw_screen(help_screen, &mut app, 20, 8),
+            vec![
+                "                    ",
+                " ╭────────────────╮ ",
+                " │/ to search, any│ ",
+                " ╰────────────────╯ ",
+                "  COMMAND LIST (1-2 ",
+                " START              ",
+                " Enter         open ",
+                "                    ",
+            ]
+        );
+        let screen = draw_screen(help_screen, &mut app, 24, 30);
+        assert_eq!(
+            screen[..18],
+            [
+                "                        ",
+                " ╭────────────────────╮ ",
+                " │/ to search, any oth│ ",
+                " ╰────────────────────╯ ",
+                "  COMMAND LIST:         ",
+                " START                  ",
+                " Enter         open sel ",
+                " n             new diar ",
+                " o             browse f ",
+                " Up            up       ",
+                " Down          down     ",
+                " p             pin or u ",
+                " d, Delete     remove f ",
+                " x             forget m ",
+                " s             sort by  ",
+                " Esc           unselect ",
+                " h             help     ",
+                " q             quit     ",
+            ]
+        );
+        assert!(screen[18..30].iter().all(|row| row == "                        "));
+        assert_eq!(
+            draw_screen(help_screen, &mut app, 80, 24),
+            vec![
+                "                                                                                ",
+                " ╭─────────────────────────────────────────╮                                    ",
+                " │/ to search, any other key to go back    │   Here to                   _.---- ",
+                " ╰─────────────────────────────────────────╯                          ,-'       ",
+                "  COMMAND LIST:                                help!                ,'          ",
+                " START                                                            ,'        . ' ",
+                " Enter         open selected                It's Elphy,         ,'    ..   .    ",
+                " n             new diary                                       /     :go. :     ",
+                " o             browse for a diary            the elegant,      |     ' .o8)     ",
+                " Up            up                                             /     :   ~:'  .  ",
+                " Down          down                           elephant.      /       ,  '       ",
+                " p             pin or unpin                                 /       ,           ",
+                " d, Delete     remove from the list          I talk,        L._    .       ,' . ",
+                " x             forget missing diaries                      /-.     :.--._,-'~~~ ",
+                " s             sort by name or recent         elegantly.  ,--.    /   .:/       ",
+                " Esc           unselect                                   |-.    /   .;'      . ",
+                " h             help                          ...._____...-|-.  ,'  .;'      .'  ",
+                " q             quit                            ~--..._____\\-_-'  .:'      .'    ",
+                "                                                ___....--~~   _.-' `.___.'   ./ ",
+                "                                                  ~~------+~~_. .    ~~    .,'  ",
+                "                                                              ~:_.' . . ._:'    ",
+                "                                             Have some           ~~-+-+~~       ",
+                "                                              commands.                         ",
+                "                                                                                ",
+            ]
+        );
+    }
 }
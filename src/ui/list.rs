@@ -0,0 +1,62 @@
+// The mru list draws only the items that fit, and remembers where each one landed so a mouse
+// click can be traced back to it.
+
+use tui::layout::Rect;
+
+// which items [start, end) to draw in `max_height` rows, given each item's height.  this mirrors
+// what tui's List does on its own: stay at `offset` if the selection is still in view, otherwise
+// scroll just far enough to show it.  with nothing selected, the first item is kept in view.
+pub fn visible_range(
+    heights: &[usize],
+    selected: Option<usize>,
+    offset: usize,
+    max_height: usize,
+) -> (usize, usize) {
+    if heights.is_empty() {
+        return (0, 0);
+    }
+    let offset = offset.min(heights.len() - 1);
+    let mut start = offset;
+    let mut end = offset;
+    let mut height = 0;
+    for item in &heights[offset..] {
+        if height + item > max_height {
+            break;
+        }
+        height += item;
+        end += 1;
+    }
+
+    let selected = selected.unwrap_or(0).min(heights.len() - 1);
+    while selected >= end {
+        height += heights[end];
+        end += 1;
+        while height > max_height {
+            height -= heights[start];
+            start += 1;
+        }
+    }
+    while selected < start {
+        start -= 1;
+        height += heights[start];
+        while height > max_height {
+            end -= 1;
+            height -= heights[end];
+        }
+    }
+    (start, end)
+}
+
+// the screen rows each drawn item covers, as (first row, row past the last, item index), stacked
+// down from the top of the area.
+pub fn item_rows(area: Rect, heights: &[usize], (start, end): (usize, usize)) -> Vec<(u16, u16, usize)> {
+    let mut used = 0;
+    let mut rows = Vec::new();
+    for (i, height) in heights.iter().enumerate().take(end).skip(start) {
+        let height = *height as u16;
+        let top = area.top() + used;
+        used += height;
+        rows.push((top, top + height, i));
+    }
+    rows
+}
@@ -0,0 +1,231 @@
+// A small markdown renderer for the message view: *italic*, **bold**, `code`, ~~strike~~,
+// [links](url), > quotes and ``` code blocks.  Messages are stored as plain text; this only changes how they
+// look.  Anything that doesn't close is shown as typed.
+
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+
+use crate::text::grapheme;
+use crate::theme::Theme;
+use crate::ui::syntax::{self, Lang};
+
+// a piece of text in one style.
+type Run = (String, Style);
+
+// render a message into lines no wider than `width`.
+pub fn render(text: &str, width: usize, theme: &Theme) -> Vec<Spans<'static>> {
+    let width = usize::max(1, width);
+    let mut lines = Vec::new();
+    // the language of the ``` block we're in, if we're in one.
+    let mut fence: Option<Option<Lang>> = None;
+    for line in text.split('\n') {
+        if let Some(name) = line.trim_start().strip_prefix("```") {
+            fence = match fence {
+                Some(_) => None,
+                None => Some(Lang::from_name(name.trim())),
+            };
+        } else if let Some(lang) = fence {
+            // code keeps its own line breaks; a line too long for the box just carries on
+            // underneath.
+            let code = line.replace('\t', "    ");
+            let runs = syntax::highlight(&code, lang, theme.code_block, theme);
+            for mut row in hard_wrap(runs, usize::max(1, width.saturating_sub(2))) {
+                let used: usize = row.iter().map(|(text, _)| grapheme::width(text)).sum();
+                row.insert(0, (" ".to_string(), theme.code_block));
+                row.push((" ".repeat(width.saturating_sub(used + 1)), theme.code_block));
+                lines.push(spans(row));
+            }
+        } else if let Some(quoted) = quote(line) {
+            // the quote bar takes up two columns on every line of the quote.
+            let runs = inline(quoted, theme.quote, theme);
+            for mut wrapped in wrap(runs, usize::max(1, width.saturating_sub(2))) {
+                wrapped.insert(0, ("│ ".to_string(), theme.quote));
+                lines.push(spans(wrapped));
+            }
+        } else {
+            for wrapped in wrap(inline(line, Style::default(), theme), width) {
+                lines.push(spans(wrapped));
+            }
+        }
+    }
+    lines
+}
+
+fn spans(runs: Vec<Run>) -> Spans<'static> {
+    Spans::from(
+        runs.into_iter()
+            .map(|(text, style)| Span::styled(text, style))
+            .collect::<Vec<_>>(),
+    )
+}
+
+// "> text" is a quote; so is ">text".
+fn quote(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix('>')?;
+    Some(rest.strip_prefix(' ').unwrap_or(rest))
+}
+
+// split a line into styled runs, dropping the markup.
+fn inline(text: &str, base: Style, theme: &Theme) -> Vec<Run> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut runs: Vec<Run> = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        // what the markup at i turns into, and where it ends.
+        let found: Option<(Vec<Run>, usize)> = match c {
+            '\\' if chars.get(i + 1).is_some_and(|n| n.is_ascii_punctuation()) => {
+                plain.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            '`' => find(&chars, i + 1, &['`']).map(|end| {
+                let code: String = chars[i + 1..end].iter().collect();
+                (vec![(code, base.patch(theme.code))], end + 1)
+            }),
+            '*' if chars.get(i + 1) == Some(&'*') => emphasis(&chars, i, 2).map(|(inner, end)| {
+                (inline(&inner, base.add_modifier(Modifier::BOLD), theme), end)
+            }),
+            '*' => emphasis(&chars, i, 1).map(|(inner, end)| {
+                (inline(&inner, base.add_modifier(Modifier::ITALIC), theme), end)
+            }),
+            '~' if chars.get(i + 1) == Some(&'~') => emphasis(&chars, i, 2).map(|(inner, end)| {
+                (inline(&inner, base.add_modifier(Modifier::CROSSED_OUT), theme), end)
+            }),
+            '[' => link(&chars, i)
+                .map(|(label, end)| (inline(&label, base.patch(theme.link), theme), end)),
+            _ => None,
+        };
+        match found {
+            Some((styled, end)) => {
+                if !plain.is_empty() {
+                    runs.push((std::mem::take(&mut plain), base));
+                }
+                runs.extend(styled);
+                i = end;
+            }
+            None => {
+                plain.push(c);
+                i += 1;
+            }
+        }
+    }
+    if !plain.is_empty() {
+        runs.push((plain, base));
+    }
+    runs
+}
+
+// the first index at or after `from` where `closing` appears.
+fn find(chars: &[char], from: usize, closing: &[char]) -> Option<usize> {
+    (from..chars.len()).find(|&i| chars[i..].starts_with(closing))
+}
+
+// `n` marker characters at `start`, some text, and the same markers again.  like markdown, the
+// text can't start or end with a space, so "2 * 3 * 4" stays as it is.
+fn emphasis(chars: &[char], start: usize, n: usize) -> Option<(String, usize)> {
+    let marker = &chars[start..start + n];
+    let open = start + n;
+    match chars.get(open) {
+        Some(c) if !c.is_whitespace() => (),
+        _ => return None,
+    }
+    let mut from = open;
+    loop {
+        let close = find(chars, from, marker)?;
+        if close > open && !chars[close - 1].is_whitespace() {
+            return Some((chars[open..close].iter().collect(), close + n));
+        }
+        from = close + 1;
+    }
+}
+
+// [label](url): the label is shown, the url stays in the text.
+fn link(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let close = find(chars, start + 1, &[']', '('])?;
+    let end = find(chars, close + 2, &[')'])?;
+    if close == start + 1 || end == close + 2 {
+        return None;
+    }
+    Some((chars[start + 1..close].iter().collect(), end + 1))
+}
+
+// fill lines up to `width` a word at a time, the way textwrap does, but keeping every piece of a
+// word in its own style.  words longer than a line are broken up.
+fn wrap(runs: Vec<Run>, width: usize) -> Vec<Vec<Run>> {
+    // each word, with the spaces before it.
+    let mut words: Vec<(Vec<Run>, Vec<Run>)> = vec![(Vec::new(), Vec::new())];
+    for (text, style) in runs {
+        let mut rest = text.as_str();
+        while !rest.is_empty() {
+            let space = rest.starts_with(char::is_whitespace);
+            let len = rest
+                .find(|c: char| c.is_whitespace() != space)
+                .unwrap_or(rest.len());
+            let (piece, after) = rest.split_at(len);
+            let last = words.last_mut().expect("there is always a word");
+            if space {
+                if !last.1.is_empty() {
+                    words.push((Vec::new(), Vec::new()));
+                }
+                words.last_mut().expect("there is always a word").0.push((piece.to_string(), style));
+            } else {
+                last.1.push((piece.to_string(), style));
+            }
+            rest = after;
+        }
+    }
+
+    let count = |runs: &[Run]| runs.iter().map(|(text, _)| grapheme::width(text)).sum::<usize>();
+    let mut lines = vec![Vec::new()];
+    let mut used = 0;
+    for (space, word) in words {
+        let space_len = count(&space);
+        let word_len = count(&word);
+        if used > 0 && used + space_len + word_len > width {
+            lines.push(Vec::new());
+            used = 0;
+        }
+        // spaces at the start of the text are kept, like indentation; at a line break they go.
+        if used > 0 || lines.len() == 1 {
+            used += space_len;
+            lines.last_mut().expect("there is always a line").extend(space);
+        }
+        for (text, style) in word {
+            push_split(&mut lines, &mut used, text, style, width);
+        }
+    }
+    lines
+}
+
+// add a piece to the last line, carrying on onto new lines if it's too wide.
+fn push_split(
+    lines: &mut Vec<Vec<Run>>,
+    used: &mut usize,
+    text: String,
+    style: Style,
+    width: usize,
+) {
+    let room = width.saturating_sub(*used);
+    for (i, piece) in grapheme::split_to_width(&text, room, width).into_iter().enumerate() {
+        if i > 0 {
+            lines.push(Vec::new());
+            *used = 0;
+        }
+        if !piece.is_empty() {
+            *used += grapheme::width(&piece);
+            lines.last_mut().expect("there is always a line").push((piece, style));
+        }
+    }
+}
+
+// cut runs into lines `width` columns wide, without looking for spaces.
+fn hard_wrap(runs: Vec<Run>, width: usize) -> Vec<Vec<Run>> {
+    let mut lines = vec![Vec::new()];
+    let mut used = 0;
+    for (text, style) in runs {
+        push_split(&mut lines, &mut used, text, style, width);
+    }
+    lines
+}
@@ -1,3 +1,36 @@
 pub mod elphy;
 pub mod edit;
 pub mod help;
+pub mod list;
+pub mod small;
+pub mod start;
+pub mod markdown;
+pub mod syntax;
+
+// the text on a test terminal, a row at a time, for checking what a screen draws.
+#[cfg(test)]
+pub fn screen_text(backend: &tui::backend::TestBackend) -> Vec<String> {
+    let buffer = backend.buffer();
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            (area.left()..area.right())
+                .map(|x| buffer.get(x, y).symbol.as_str())
+                .collect()
+        })
+        .collect()
+}
+
+// what one of the app's screens draws at a size, a row at a time.
+#[cfg(test)]
+pub fn draw_screen(
+    screen: fn(&mut tui::Frame<tui::backend::TestBackend>, &mut crate::app::app::App),
+    app: &mut crate::app::app::App,
+    width: u16,
+    height: u16,
+) -> Vec<String> {
+    let backend = tui::backend::TestBackend::new(width, height);
+    let mut terminal = tui::Terminal::new(backend).unwrap();
+    terminal.draw(|f| screen(f, app)).unwrap();
+    screen_text(terminal.backend())
+}
@@ -0,0 +1,96 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+
+// Every screen squeezes itself down to this size; anything smaller gets a note instead.
+pub const MIN_WIDTH: u16 = 20;
+pub const MIN_HEIGHT: u16 = 8;
+
+pub fn is_too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+// shown in place of any screen when the terminal is too small to draw it.
+pub fn too_small_screen<B: Backend>(f: &mut Frame<B>) {
+    let area = f.size();
+    let text = vec![
+        Spans::from(Span::styled(
+            "Terminal too small",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(format!("{}x{}", area.width, area.height)),
+        Spans::from(format!("need {}x{}", MIN_WIDTH, MIN_HEIGHT)),
+    ];
+    // keep it roughly in the middle, if there's even room for that.
+    let top = area.height.saturating_sub(text.len() as u16) / 2;
+    let middle = Rect {
+        y: area.y + top,
+        height: area.height - top,
+        ..area
+    };
+    let note = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(note, middle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::{backend::TestBackend, Terminal};
+
+    fn render(width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(too_small_screen).unwrap();
+        crate::ui::screen_text(terminal.backend())
+    }
+
+    #[test]
+    fn too_small_is_either_side_of_the_minimum() {
+        assert!(!is_too_small(Rect::new(0, 0, 20, 8)));
+        assert!(!is_too_small(Rect::new(0, 0, 120, 40)));
+        assert!(is_too_small(Rect::new(0, 0, 19, 8)));
+        assert!(is_too_small(Rect::new(0, 0, 20, 7)));
+        assert!(is_too_small(Rect::new(0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn note_says_how_big_it_is_and_needs_to_be() {
+        assert_eq!(
+            render(19, 6),
+            vec![
+                "                   ",
+                "Terminal too small ",
+                "       19x6        ",
+                "     need 20x8     ",
+                "                   ",
+                "                   ",
+            ]
+        );
+        assert_eq!(
+            render(23, 8),
+            vec![
+                "                       ",
+                "                       ",
+                "  Terminal too small   ",
+                "         23x8          ",
+                "       need 20x8       ",
+                "                       ",
+                "                       ",
+                "                       ",
+            ]
+        );
+    }
+
+    #[test]
+    fn note_fits_in_anything() {
+        for (width, height) in [(1, 1), (5, 2), (19, 1), (2, 7)] {
+            assert_eq!(render(width, height).len(), height as usize);
+        }
+    }
+}
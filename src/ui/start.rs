@@ -0,0 +1,618 @@
+// The start screen, and the two screens it leads to: the file browser and the new diary form.
+use chrono::prelude::Local;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::app::app::*;
+use crate::app::start::{FormField, Summary};
+use crate::config::{MruEntry, MruSort};
+use crate::text::grapheme;
+use crate::ui::list;
+
+// every mru entry takes up this many rows: name and details, last message, path.
+const ENTRY_HEIGHT: usize = 3;
+
+pub fn start_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let (title_area, list_area) = columns(f.size());
+
+    let heading = match app.config.mru_sort {
+        MruSort::Recent => "MOST RECENTLY USED: ",
+        MruSort::Name => "DIARIES, BY NAME: ",
+    };
+    let mru_title = Paragraph::new(Span::styled(
+        heading,
+        Style::default().add_modifier(Modifier::ITALIC),
+    ))
+    .block(bordered(app));
+    f.render_widget(mru_title, title_area);
+
+    let inner = Block::default().borders(Borders::ALL).inner(list_area);
+    if app.config.mru.is_empty() {
+        let empty = Paragraph::new(vec![
+            Spans::from("No diaries yet."),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                "n for a new one, o to look for one",
+                app.theme.hint,
+            )),
+        ])
+        .block(bordered(app));
+        f.render_widget(empty, list_area);
+        app.mouse.list = inner;
+        app.mouse.rows.clear();
+        status_row(f, app, None);
+        return;
+    }
+
+    // the mru list is drawn in the config's order, pinned diaries first.
+    let heights = vec![ENTRY_HEIGHT; app.config.mru.len()];
+    let (start, end) = list::visible_range(
+        &heights,
+        app.select_state.selected(),
+        app.list_offset,
+        inner.height as usize,
+    );
+    app.list_offset = start;
+    let mut list_state = ListState::default();
+    list_state.select(app.select_state.selected().and_then(|i| i.checked_sub(start)));
+    app.mouse.list = inner;
+    app.mouse.rows = list::item_rows(inner, &heights, (start, end));
+
+    let width = inner.width as usize;
+    let items: Vec<ListItem> = app
+        .config
+        .mru
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(end - start)
+        .map(|(i, entry)| ListItem::new(entry_lines(app, entry, app.recent.get(i), width)))
+        .collect();
+    let mru_list = List::new(items)
+        .block(bordered(app))
+        .highlight_style(app.theme.highlight);
+    f.render_stateful_widget(mru_list, list_area, &mut list_state);
+
+    status_row(f, app, None);
+}
+
+// the three lines of an mru entry.
+fn entry_lines(
+    app: &App,
+    entry: &MruEntry,
+    summary: Option<&Result<Summary, String>>,
+    width: usize,
+) -> Text<'static> {
+    let path = &entry.path;
+    let title = entry.title();
+    let indent = |s: &str| format!("  {}", grapheme::truncate(s, width.saturating_sub(2)));
+    let (details, preview) = match summary {
+        Some(Ok(summary)) => {
+            let count = match summary.messages {
+                1 => String::from("1 message"),
+                n => format!("{} messages", n),
+            };
+            let details = match summary.modified {
+                Some(time) => format!(
+                    "{} · {}",
+                    count,
+                    time.with_timezone(&Local).format("%Y-%m-%d %I:%M %p")
+                ),
+                None => count,
+            };
+            let preview = match &summary.last {
+                Some(last) => Span::styled(
+                    indent(last),
+                    Style::default().add_modifier(Modifier::ITALIC),
+                ),
+                None => Span::styled(indent("nothing written yet"), app.theme.hint),
+            };
+            (Span::styled(details, app.theme.hint), preview)
+        }
+        Some(Err(e)) => (
+            Span::styled(String::from("can't be opened"), app.theme.notice),
+            Span::styled(indent(e), app.theme.notice),
+        ),
+        None => (Span::raw(String::new()), Span::raw(String::new())),
+    };
+    let pin = if entry.pinned { "★ " } else { "" };
+    let title_width = width.saturating_sub(grapheme::width(&details.content) + grapheme::width(pin) + 2);
+    Text::from(vec![
+        Spans::from(vec![
+            Span::styled(pin, app.theme.notice),
+            Span::styled(
+                grapheme::truncate(&title, usize::max(1, title_width)),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  "),
+            details,
+        ]),
+        Spans::from(preview),
+        Spans::from(Span::styled(indent(path), app.theme.hint)),
+    ])
+}
+
+pub fn browse_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let (title_area, list_area) = columns(f.size());
+    let browser = match &mut app.browser {
+        Some(browser) => browser,
+        None => return,
+    };
+
+    let dir = browser.dir.to_string_lossy().into_owned();
+    let room = title_area.width.saturating_sub(2) as usize;
+    let title = Paragraph::new(Span::styled(
+        grapheme::truncate(&dir, room),
+        Style::default().add_modifier(Modifier::ITALIC),
+    ))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(app.theme.border)
+            .border_type(app.theme.border_type),
+    );
+    f.render_widget(title, title_area);
+
+    let inner = Block::default().borders(Borders::ALL).inner(list_area);
+    let heights = vec![1; browser.entries.len()];
+    let (start, end) = list::visible_range(
+        &heights,
+        Some(browser.selected),
+        browser.offset,
+        inner.height as usize,
+    );
+    browser.offset = start;
+    let mut list_state = ListState::default();
+    list_state.select(browser.selected.checked_sub(start));
+    app.mouse.list = inner;
+    app.mouse.rows = list::item_rows(inner, &heights, (start, end));
+
+    let width = inner.width as usize;
+    let items: Vec<ListItem> = browser.entries[start..end]
+        .iter()
+        .map(|entry| {
+            if entry.is_dir {
+                ListItem::new(Span::styled(
+                    grapheme::truncate(&format!("{}/", entry.name), width),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                ListItem::new(grapheme::truncate(&entry.name, width))
+            }
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(app.theme.border)
+                .border_type(app.theme.border_type),
+        )
+        .highlight_style(app.theme.highlight);
+    f.render_stateful_widget(list, list_area, &mut list_state);
+
+    let note = match &browser.error {
+        Some(e) => Some(format!("can't read this folder: {}", e)),
+        None if browser.entries.iter().all(|e| e.is_dir) => {
+            Some(String::from("no diaries in this folder"))
+        }
+        None => None,
+    };
+    status_row(f, app, note);
+}
+
+pub fn new_diary_screen<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let area = f.size();
+    let form = match &app.new_diary {
+        Some(form) => form,
+        None => return,
+    };
+    let width = u16::min(area.width, 64);
+    let height = u16::min(area.height, 8);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    let block = Block::default()
+        .title(Span::styled(
+            " NEW DIARY ",
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .border_type(app.theme.border_type);
+    let inner = block.inner(popup);
+
+    // each field shows as much of its end as fits, so the cursor is always in view.
+    const LABEL_WIDTH: usize = 10;
+    let room = (inner.width as usize).saturating_sub(LABEL_WIDTH + 1);
+    let field = |label: &str, value: &str, active: bool| {
+        let label_style = if active {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            app.theme.hint
+        };
+        Spans::from(vec![
+            Span::styled(format!("{:<width$}", label, width = LABEL_WIDTH), label_style),
+            Span::raw(grapheme::tail(value, room).to_string()),
+        ])
+    };
+    let inner_width = inner.width as usize;
+    let mut lines = vec![
+        field("Name", &form.name, form.field == FormField::Name),
+        field("Location", &form.location, form.field == FormField::Location),
+        Spans::from(""),
+    ];
+    if !form.name.trim().is_empty() {
+        let path = form.path().to_string_lossy().into_owned();
+        lines.push(Spans::from(Span::styled(
+            grapheme::truncate(&format!("saves to {}", path), inner_width),
+            app.theme.hint,
+        )));
+    }
+    if let Some(e) = &form.error {
+        lines.push(Spans::from(Span::styled(
+            grapheme::truncate(e, inner_width),
+            app.theme.notice,
+        )));
+    }
+
+    f.render_widget(Clear, popup);
+    f.render_widget(Paragraph::new(lines).block(block), popup);
+
+    let (row, value) = match form.field {
+        FormField::Name => (0, &form.name),
+        FormField::Location => (1, &form.location),
+    };
+    if row < inner.height {
+        let col = LABEL_WIDTH + grapheme::width(grapheme::tail(value, room));
+        f.set_cursor(
+            inner.x + u16::min(col as u16, inner.width.saturating_sub(1)),
+            inner.y + row,
+        );
+    }
+}
+
+// the start and browse screens: a title box over a list, in the middle of the screen.
+fn columns(area: Rect) -> (Rect, Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(15),
+                Constraint::Percentage(70),
+                Constraint::Percentage(15),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let center_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Percentage(100)].as_ref())
+        .split(chunks[1]);
+    (center_chunks[0], center_chunks[1])
+}
+
+fn bordered(app: &App) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .border_type(app.theme.border_type)
+}
+
+// the bottom row: a note from the screen, or whatever went wrong last, like a diary that
+// wouldn't open.
+fn status_row<B: Backend>(f: &mut Frame<B>, app: &App, note: Option<String>) {
+    let text = if app.status_msg.is_empty() {
+        match note {
+            Some(note) => note,
+            None => return,
+        }
+    } else {
+        app.status_msg.clone()
+    };
+    let area = f.size();
+    let bottom = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1);
+    let status = Paragraph::new(grapheme::truncate(&text, area.width as usize))
+        .style(app.theme.message_bar);
+    f.render_widget(status, bottom);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::start::{Browser, Entry, NewDiaryForm};
+    use crate::config::DiaryConfig;
+    use crate::ui::draw_screen;
+    use std::path::PathBuf;
+
+    // every screen is drawn at the minimum size, narrow but tall, and at a usual size.
+    fn app() -> App {
+        App::from_config(DiaryConfig::in_memory(), Vec::new())
+    }
+
+    // three diaries, one pinned and one gone, without reading any files for them.
+    fn with_mru(app: &mut App) {
+        let entry = |path: &str, pinned| MruEntry {
+            path: path.to_string(),
+            opened: None,
+            pinned,
+        };
+        app.config.mru = vec![
+            entry("/diaries/work.diary", true),
+            entry("/diaries/home.diary", false),
+            entry("/gone/old.diary", false),
+        ];
+        app.recent = vec![
+            Ok(Summary {
+                messages: 3,
+                modified: None,
+                last: Some(String::from("shipped it")),
+            }),
+            Ok(Summary {
+                messages: 1,
+                modified: None,
+                last: None,
+            }),
+            Err(String::from("missing")),
+        ];
+        app.select_state.select(Some(0));
+    }
+
+    fn entry(name: &str, is_dir: bool) -> Entry {
+        Entry {
+            name: name.to_string(),
+            path: PathBuf::from("/diaries").join(name),
+            is_dir,
+        }
+    }
+
+    #[test]
+    fn start_with_no_diaries() {
+        let mut app = app();
+        assert_eq!(
+            draw_screen(start_screen, &mut app, 20, 8),
+            vec![
+                "   ╭────────────╮   ",
+                "   │MOST RECENTL│   ",
+                "   ╰────────────╯   ",
+                "   ╭────────────╮   ",
+                "   │No diaries y│   ",
+                "   │            │   ",
+                "   │n for a new │   ",
+                "   ╰────────────╯   ",
+            ]
+        );
+        let screen = draw_screen(start_screen, &mut app, 24, 30);
+        assert_eq!(
+            screen[..7],
+            [
+                "   ╭──────────────╮     ",
+                "   │MOST RECENTLY │     ",
+                "   ╰──────────────╯     ",
+                "   ╭──────────────╮     ",
+                "   │No diaries yet│     ",
+                "   │              │     ",
+                "   │n for a new on│     ",
+            ]
+        );
+        assert!(screen[7..29].iter().all(|row| row == "   │              │     "));
+        assert_eq!(screen[29], "   ╰──────────────╯     ");
+        let screen = draw_screen(start_screen, &mut app, 80, 24);
+        assert_eq!(
+            screen[..7],
+            [
+                "            ╭──────────────────────────────────────────────────────╮            ",
+                "            │MOST RECENTLY USED:                                   │            ",
+                "            ╰──────────────────────────────────────────────────────╯            ",
+                "            ╭──────────────────────────────────────────────────────╮            ",
+                "            │No diaries yet.                                       │            ",
+                "            │                                                      │            ",
+                "            │n for a new one, o to look for one                    │            ",
+            ]
+        );
+        assert!(screen[7..23].iter().all(|row| row == "            │                                                      │            "));
+        assert_eq!(screen[23], "            ╰──────────────────────────────────────────────────────╯            ");
+    }
+
+    #[test]
+    fn start_with_diaries() {
+        let mut app = app();
+        with_mru(&mut app);
+        assert_eq!(
+            draw_screen(start_screen, &mut app, 20, 8),
+            vec![
+                "   ╭────────────╮   ",
+                "   │MOST RECENTL│   ",
+                "   ╰────────────╯   ",
+                "   ╭────────────╮   ",
+                "   │★ …  3 messa│   ",
+                "   │  shipped it│   ",
+                "   │  /diaries/…│   ",
+                "   ╰────────────╯   ",
+            ]
+        );
+        let screen = draw_screen(start_screen, &mut app, 24, 30);
+        assert_eq!(
+            screen[..13],
+            [
+                "   ╭──────────────╮     ",
+                "   │MOST RECENTLY │     ",
+                "   ╰──────────────╯     ",
+                "   ╭──────────────╮     ",
+                "   │★ …  3 message│     ",
+                "   │  shipped it  │     ",
+                "   │  /diaries/wo…│     ",
+                "   │ho…  1 message│     ",
+                "   │  nothing wri…│     ",
+                "   │  /diaries/ho…│     ",
+                "   │…  can't be op│     ",
+                "   │  missing     │     ",
+                "   │  /gone/old.d…│     ",
+            ]
+        );
+        assert!(screen[13..29].iter().all(|row| row == "   │              │     "));
+        assert_eq!(screen[29], "   ╰──────────────╯     ");
+        let screen = draw_screen(start_screen, &mut app, 80, 24);
+        assert_eq!(
+            screen[..13],
+            [
+                "            ╭──────────────────────────────────────────────────────╮            ",
+                "            │MOST RECENTLY USED:                                   │            ",
+                "            ╰──────────────────────────────────────────────────────╯            ",
+                "            ╭──────────────────────────────────────────────────────╮            ",
+                "            │★ work  3 messages                                    │            ",
+                "            │  shipped it                                          │            ",
+                "            │  /diaries/work.diary                                 │            ",
+                "            │home  1 message                                       │            ",
+                "            │  nothing written yet                                 │            ",
+                "            │  /diaries/home.diary                                 │            ",
+                "            │old  can't be opened                                  │            ",
+                "            │  missing                                             │            ",
+                "            │  /gone/old.diary                                     │            ",
+            ]
+        );
+        assert!(screen[13..23].iter().all(|row| row == "            │                                                      │            "));
+        assert_eq!(screen[23], "            ╰──────────────────────────────────────────────────────╯            ");
+    }
+
+    #[test]
+    fn browse() {
+        let mut app = app();
+        app.browser = Some(Browser {
+            dir: PathBuf::from("/diaries"),
+            entries: vec![
+                entry("..", true),
+                entry("notes", true),
+                entry("home.diary", false),
+                entry("work.diary", false),
+            ],
+            selected: 2,
+            offset: 0,
+            error: None,
+        });
+        assert_eq!(
+            draw_screen(browse_screen, &mut app, 20, 8),
+            vec![
+                "   ╭────────────╮   ",
+                "   │/diaries    │   ",
+                "   ╰────────────╯   ",
+                "   ╭────────────╮   ",
+                "   │../         │   ",
+                "   │notes/      │   ",
+                "   │home.diary  │   ",
+                "   ╰────────────╯   ",
+            ]
+        );
+        let screen = draw_screen(browse_screen, &mut app, 24, 30);
+        assert_eq!(
+            screen[..8],
+            [
+                "   ╭──────────────╮     ",
+                "   │/diaries      │     ",
+                "   ╰──────────────╯     ",
+                "   ╭──────────────╮     ",
+                "   │../           │     ",
+                "   │notes/        │     ",
+                "   │home.diary    │     ",
+                "   │work.diary    │     ",
+            ]
+        );
+        assert!(screen[8..29].iter().all(|row| row == "   │              │     "));
+        assert_eq!(screen[29], "   ╰──────────────╯     ");
+        let screen = draw_screen(browse_screen, &mut app, 80, 24);
+        assert_eq!(
+            screen[..8],
+            [
+                "            ╭──────────────────────────────────────────────────────╮            ",
+                "            │/diaries                                              │            ",
+                "            ╰──────────────────────────────────────────────────────╯            ",
+                "            ╭──────────────────────────────────────────────────────╮            ",
+                "            │../                                                   │            ",
+                "            │notes/                                                │            ",
+                "            │home.diary                                            │            ",
+                "            │work.diary                                            │            ",
+            ]
+        );
+        assert!(screen[8..23].iter().all(|row| row == "            │                                                      │            "));
+        assert_eq!(screen[23], "            ╰──────────────────────────────────────────────────────╯            ");
+    }
+
+    #[test]
+    fn new_diary() {
+        let mut app = app();
+        let mut form = NewDiaryForm::new(String::from("/diaries"));
+        form.name = String::from("trip");
+        app.new_diary = Some(form);
+        assert_eq!(
+            draw_screen(new_diary_screen, &mut app, 20, 8),
+            vec![
+                "╭ NEW DIARY ───────╮",
+                "│Name      trip    │",
+                "│Location  diaries │",
+                "│                  │",
+                "│saves to /diaries…│",
+                "│                  │",
+                "│                  │",
+                "╰──────────────────╯",
+            ]
+        );
+        let screen = draw_screen(new_diary_screen, &mut app, 24, 30);
+        assert!(screen[0..11].iter().all(|row| row == "                        "));
+        assert_eq!(
+            screen[11..],
+            [
+                "╭ NEW DIARY ───────────╮",
+                "│Name      trip        │",
+                "│Location  /diaries    │",
+                "│                      │",
+                "│saves to /diaries/tri…│",
+                "│                      │",
+                "│                      │",
+                "╰──────────────────────╯",
+                "                        ",
+                "                        ",
+                "                        ",
+                "                        ",
+                "                        ",
+                "                        ",
+                "                        ",
+                "                        ",
+                "                        ",
+                "                        ",
+                "                        ",
+            ]
+        );
+        let screen = draw_screen(new_diary_screen, &mut app, 80, 24);
+        assert!(screen[0..8].iter().all(|row| row == "                                                                                "));
+        assert_eq!(
+            screen[8..],
+            [
+                "        ╭ NEW DIARY ───────────────────────────────────────────────────╮        ",
+                "        │Name      trip                                                │        ",
+                "        │Location  /diaries                                            │        ",
+                "        │                                                              │        ",
+                "        │saves to /diaries/trip.diary                                  │        ",
+                "        │                                                              │        ",
+                "        │                                                              │        ",
+                "        ╰──────────────────────────────────────────────────────────────╯        ",
+                "                                                                                ",
+                "                                                                                ",
+                "                                                                                ",
+                "                                                                                ",
+                "                                                                                ",
+                "                                                                                ",
+                "                                                                                ",
+                "                                                                                ",
+            ]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+// Keyword highlighting for fenced code blocks in messages.  It's nowhere near a real parser: it
+// goes a line at a time and knows about words, numbers, strings and line comments, which is
+// enough to make a pasted snippet readable.
+
+use tui::style::Style;
+
+use crate::theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    Rust,
+    Python,
+    Shell,
+    Json,
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+    "use", "where", "while",
+];
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in",
+    "local", "return", "then", "until", "while", "echo", "cd", "exit", "set", "unset", "source",
+];
+const JSON_KEYWORDS: &[&str] = &["true", "false", "null"];
+
+impl Lang {
+    // the language named after the ``` of a fence, if it's one we know.
+    pub fn from_name(name: &str) -> Option<Lang> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Some(Lang::Rust),
+            "python" | "py" | "python3" => Some(Lang::Python),
+            "sh" | "shell" | "bash" | "zsh" | "console" => Some(Lang::Shell),
+            "json" | "jsonl" => Some(Lang::Json),
+            _ => None,
+        }
+    }
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Lang::Rust => RUST_KEYWORDS,
+            Lang::Python => PYTHON_KEYWORDS,
+            Lang::Shell => SHELL_KEYWORDS,
+            Lang::Json => JSON_KEYWORDS,
+        }
+    }
+    fn line_comment(self) -> Option<&'static str> {
+        match self {
+            Lang::Rust => Some("//"),
+            Lang::Python | Lang::Shell => Some("#"),
+            Lang::Json => None,
+        }
+    }
+    fn quotes(self) -> &'static [char] {
+        match self {
+            // a ' in rust is usually a lifetime, not a char.
+            Lang::Rust | Lang::Json => &['"'],
+            Lang::Python | Lang::Shell => &['"', '\''],
+        }
+    }
+}
+
+// split one line of code into styled pieces, on top of `base`.
+pub fn highlight(
+    line: &str,
+    lang: Option<Lang>,
+    base: Style,
+    theme: &Theme,
+) -> Vec<(String, Style)> {
+    let lang = match lang {
+        Some(lang) => lang,
+        None => return vec![(line.to_string(), base)],
+    };
+    let chars: Vec<char> = line.chars().collect();
+    let mut runs: Vec<(String, Style)> = Vec::new();
+    let mut push = |text: String, style: Style| match runs.last_mut() {
+        Some((last, last_style)) if *last_style == style => last.push_str(&text),
+        _ => runs.push((text, style)),
+    };
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..].iter().collect();
+        let start = i;
+        if lang.line_comment().is_some_and(|prefix| rest.starts_with(prefix)) {
+            push(rest, base.patch(theme.comment));
+            break;
+        } else if lang == Lang::Rust && rest.starts_with("/*") {
+            i = match rest[2..].find("*/") {
+                Some(end) => i + 2 + rest[2..2 + end].chars().count() + 2,
+                None => chars.len(),
+            };
+            push(chars[start..i].iter().collect(), base.patch(theme.comment));
+        } else if lang.quotes().contains(&c) {
+            // to the closing quote, skipping escaped ones.
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = usize::min(i + 1, chars.len());
+            push(chars[start..i].iter().collect(), base.patch(theme.string));
+        } else if c.is_ascii_digit() {
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            push(chars[start..i].iter().collect(), base.patch(theme.number));
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let style = if lang.keywords().contains(&word.as_str()) {
+                base.patch(theme.keyword)
+            } else {
+                base
+            };
+            push(word, style);
+        } else {
+            i += 1;
+            push(c.to_string(), base);
+        }
+    }
+    runs
+}
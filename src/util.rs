@@ -1,13 +1,19 @@
 
-use chrono;
 
 
-pub const APP_NAME: &'static str = "dated-diary";
+pub const APP_NAME: &str = "dated-diary";
 
 // Produce a formatted string describing the current time.
 // Need YYYY/MM/DD HH:MM:SS
 pub fn current_time_string() -> String {
     let now = chrono::offset::Local::now();
-    format!("{}", now.format("%Y-%m-%d %I:%M:%S %p"))
+    format!("{}", now.format(TIME_FORMAT))
 }
 
+// the same, for a time out of a diary, which is kept in utc.
+pub fn local_time_string(time: chrono::DateTime<chrono::Utc>) -> String {
+    format!("{}", time.with_timezone(&chrono::offset::Local).format(TIME_FORMAT))
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %I:%M:%S %p";
+